@@ -0,0 +1,36 @@
+//! Times revealing a 1000x1000 board without mines, where a single click floods every tile
+//!
+//! `click` is the flood fill alone, `reveal` also keeps every tile it changed for undo.
+//!
+//! Run with `cargo bench --bench flood_fill --no-default-features`.
+
+use minesweeper_rs::{Data, GameStatus};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+const SIZE: usize = 1000;
+const RUNS: u32 = 20;
+
+fn main() {
+    bench("click", Data::click);
+    bench("reveal", Data::reveal);
+}
+
+fn bench(label: &str, make_move: fn(&mut Data, usize, usize)) {
+    let mut total = Duration::ZERO;
+    let mut fastest = Duration::MAX;
+    for _ in 0..RUNS {
+        let mut data = Data::new(0, SIZE, SIZE);
+        let start = Instant::now();
+        make_move(&mut data, SIZE / 2, SIZE / 2);
+        let time = start.elapsed();
+        assert_eq!(black_box(&data).status(), GameStatus::Won);
+        total += time;
+        fastest = fastest.min(time);
+    }
+    println!(
+        "{label} flood fill {SIZE}x{SIZE}: {:.2?} average, {:.2?} fastest over {RUNS} runs",
+        total / RUNS,
+        fastest
+    );
+}
@@ -1,3 +0,0 @@
-fn main() {
-    println!("cargo:rustc-link-arg=/STACK:10485760")
-}
\ No newline at end of file
@@ -0,0 +1,172 @@
+//! Command line options for picking the board, shared by every frontend on top of options of their own
+//!
+//! Each frontend checks the board against its own limits, the window only goes so big while a terminal
+//! scrolls.
+
+use crate::config::Config;
+use crate::GameConfig;
+
+/// The usage of `program`, `width` and `height` describing the sizes it allows and `options` listing the ones
+/// only it takes, a line each
+pub fn usage(program: &str, width: &str, height: &str, options: &[&str]) -> String {
+    let mut usage = format!(
+        "usage: {program} [options]
+
+  --difficulty <beginner|intermediate|expert>  start from a standard board instead of the last one played
+  --width <tiles>                              {width}
+  --height <tiles>                             {height}
+  --mines <count>                              leaving at least 9 tiles free for the first click
+  --seed <u64>                                 play a specific board
+  --no-guess                                   only place mines that can be solved without guessing"
+    );
+    for option in options
+        .iter()
+        .chain(&["--help                                       show this message"])
+    {
+        usage.push_str("\n  ");
+        usage.push_str(option);
+    }
+    usage
+}
+
+/// The board the options ask for, the size ones taking precedence over a difficulty wherever they come
+pub struct BoardOptions {
+    difficulty: GameConfig,
+    width: Option<usize>,
+    height: Option<usize>,
+    mines: Option<u32>,
+    seed: Option<u64>,
+    no_guess: bool,
+}
+
+impl BoardOptions {
+    /// Starts from the board in the config, or beginner if `check` refuses it since a hand edited config
+    /// shouldn't make the game refuse to start
+    pub fn new(config: &Config, check: impl Fn(GameConfig) -> Result<(), String>) -> BoardOptions {
+        let board = GameConfig::new(config.width, config.height, config.mines);
+        BoardOptions {
+            difficulty: if check(board).is_ok() {
+                board
+            } else {
+                GameConfig::BEGINNER
+            },
+            width: None,
+            height: None,
+            mines: None,
+            seed: None,
+            no_guess: config.no_guess,
+        }
+    }
+
+    /// Takes `arg` if it's one of the board options, with `value` giving the argument after it, and returns
+    /// whether it was
+    pub fn parse(
+        &mut self,
+        arg: &str,
+        value: impl FnOnce(&str) -> Result<String, String>,
+    ) -> Result<bool, String> {
+        match arg {
+            "--difficulty" => {
+                self.difficulty = match value("a difficulty")?.as_str() {
+                    "beginner" => GameConfig::BEGINNER,
+                    "intermediate" => GameConfig::INTERMEDIATE,
+                    "expert" => GameConfig::EXPERT,
+                    other => {
+                        return Err(format!(
+                            "unknown difficulty {other}, expected beginner, intermediate or expert"
+                        ))
+                    }
+                }
+            }
+            "--width" => self.width = Some(number(arg, &value("a number of tiles")?)?),
+            "--height" => self.height = Some(number(arg, &value("a number of tiles")?)?),
+            "--mines" => self.mines = Some(number(arg, &value("a number of mines")?)?),
+            "--seed" => self.seed = Some(number(arg, &value("an unsigned 64-bit integer")?)?),
+            "--no-guess" => self.no_guess = true,
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    /// Whether a board was picked by hand, rather than by difficulty or the config
+    pub fn is_custom(&self) -> bool {
+        self.width.is_some() || self.height.is_some() || self.mines.is_some() || self.seed.is_some()
+    }
+
+    pub fn board(&self) -> GameConfig {
+        GameConfig {
+            width: self.width.unwrap_or(self.difficulty.width),
+            height: self.height.unwrap_or(self.difficulty.height),
+            mines: self.mines.unwrap_or(self.difficulty.mines),
+            seed: self.seed,
+            no_guess: self.no_guess,
+        }
+    }
+}
+
+fn number<T: std::str::FromStr>(arg: &str, value: &str) -> Result<T, String> {
+    value
+        .parse()
+        .map_err(|_| format!("{value} isn't a valid number for {arg}"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(args: &[&str]) -> Result<GameConfig, String> {
+        let config = Config {
+            width: 16,
+            height: 16,
+            mines: 40,
+            ..Config::default()
+        };
+        let mut options = BoardOptions::new(&config, |_| Ok(()));
+        let mut args = args.iter().map(|arg| arg.to_string());
+        while let Some(arg) = args.next() {
+            let value = |expected: &str| {
+                args.next()
+                    .ok_or_else(|| format!("{arg} expects {expected}"))
+            };
+            if !options.parse(&arg, value)? {
+                return Err(format!("unknown argument {arg}"));
+            }
+        }
+        Ok(options.board())
+    }
+
+    #[test]
+    fn sizes_win_over_a_difficulty_in_any_order() {
+        assert_eq!(parse(&[]), Ok(GameConfig::INTERMEDIATE));
+        assert_eq!(
+            parse(&[
+                "--mines",
+                "120",
+                "--difficulty",
+                "expert",
+                "--seed",
+                "3",
+                "--no-guess"
+            ]),
+            Ok(GameConfig {
+                mines: 120,
+                seed: Some(3),
+                no_guess: true,
+                ..GameConfig::EXPERT
+            })
+        );
+        assert!(parse(&["--width"]).is_err());
+        assert!(parse(&["--seed", "-1"]).is_err());
+        assert!(parse(&["--difficulty", "hard"]).is_err());
+        assert!(parse(&["--theme", "dark"]).is_err());
+
+        // boards the frontend refuses go back to beginner
+        let config = Config::default();
+        let options =
+            BoardOptions::new(&Config { width: 0, ..config }, |board| match board.width {
+                0 => Err("no tiles".to_owned()),
+                _ => Ok(()),
+            });
+        assert_eq!(options.board(), GameConfig::BEGINNER);
+    }
+}
@@ -0,0 +1,196 @@
+use crate::window::Theme;
+use crate::{MAX_SIZE, MIN_SIZE};
+use minesweeper_rs::args::{self, BoardOptions};
+use minesweeper_rs::config::Config;
+use minesweeper_rs::replay::Replay;
+use std::path::Path;
+
+pub fn usage() -> String {
+    args::usage(
+        "minesweeper-rs",
+        &format!("{} to {}", MIN_SIZE.0, MAX_SIZE.0),
+        &format!("{} to {}", MIN_SIZE.1, MAX_SIZE.1),
+        &[
+            "--theme <light|dark>                         the last one picked, or a random one otherwise",
+            "--pack <name>                                a theme pack from the themes folder of the config dir",
+            "--replay <path>                              watch a recorded game instead of playing",
+        ],
+    )
+}
+
+/// What the game was launched with on top of the saved config, already checked to make a playable board
+pub struct Options {
+    pub width: usize,
+    pub height: usize,
+    pub mines: u32,
+    pub seed: Option<u64>,
+    pub no_guess: bool,
+    pub theme: Option<Theme>,
+    pub pack: Option<String>,
+    pub replay: Option<Replay>,
+}
+
+/// `Ok(None)` when only the usage was asked for
+pub fn parse(
+    mut args: impl Iterator<Item = String>,
+    config: &Config,
+) -> Result<Option<Options>, String> {
+    let mut board = BoardOptions::new(config, |board| {
+        check_board(board.width, board.height, board.mines)
+    });
+    let mut theme = config.theme;
+    let mut pack = config.pack.clone();
+    let mut replay = None;
+    while let Some(arg) = args.next() {
+        let mut value = |expected: &str| {
+            args.next()
+                .ok_or_else(|| format!("{arg} expects {expected}"))
+        };
+        if board.parse(&arg, &mut value)? {
+            continue;
+        }
+        match arg.as_str() {
+            "--theme" => {
+                theme = Some(match value("a theme")?.as_str() {
+                    "light" => Theme::Light,
+                    "dark" => Theme::Dark,
+                    other => return Err(format!("unknown theme {other}, expected light or dark")),
+                })
+            }
+            "--pack" => pack = Some(value("a pack name")?),
+            "--replay" => {
+                let path = value("a path")?;
+                replay = Some(
+                    Replay::load(Path::new(&path))
+                        .map_err(|error| format!("couldn't load replay {path}: {error}"))?,
+                );
+            }
+            "--help" | "-h" => return Ok(None),
+            _ => return Err(format!("unknown argument {arg}")),
+        }
+    }
+
+    if replay.is_some() && board.is_custom() {
+        return Err("a replay already has its own board".to_owned());
+    }
+    let board = board.board();
+    let (width, height, mines) = match &replay {
+        Some(replay) => (replay.width, replay.height, replay.mines),
+        None => (board.width, board.height, board.mines),
+    };
+    check_board(width, height, mines)?;
+
+    Ok(Some(Options {
+        width,
+        height,
+        mines,
+        seed: board.seed,
+        no_guess: board.no_guess,
+        theme,
+        pack,
+        replay,
+    }))
+}
+
+/// The limits resizing the window and the arrow keys keep boards within
+pub fn check_board(width: usize, height: usize, mines: u32) -> Result<(), String> {
+    if !(MIN_SIZE.0..=MAX_SIZE.0).contains(&width) {
+        return Err(format!(
+            "a board can't be {width} tiles wide, it has to be between {} and {}",
+            MIN_SIZE.0, MAX_SIZE.0
+        ));
+    }
+    if !(MIN_SIZE.1..=MAX_SIZE.1).contains(&height) {
+        return Err(format!(
+            "a board can't be {height} tiles high, it has to be between {} and {}",
+            MIN_SIZE.1, MAX_SIZE.1
+        ));
+    }
+    // the first click clears a 3x3 square, the same limit the up arrow stops at
+    if mines as usize + 9 > width * height {
+        return Err(format!(
+            "{mines} mines don't fit on a {width}x{height} board, it takes at most {}",
+            width * height - 9
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(args: &[&str], config: &Config) -> Result<Option<Options>, String> {
+        parse(args.iter().map(|arg| arg.to_string()), config)
+    }
+
+    fn board(options: Options) -> (usize, usize, u32) {
+        (options.width, options.height, options.mines)
+    }
+
+    #[test]
+    fn boards_come_from_the_config_difficulty_then_arguments() {
+        let config = Config {
+            width: 16,
+            height: 16,
+            mines: 40,
+            ..Config::default()
+        };
+        assert_eq!(
+            run(&[], &config).map(|options| board(options.unwrap())),
+            Ok((16, 16, 40))
+        );
+        assert_eq!(
+            run(&["--difficulty", "expert", "--mines", "120"], &config)
+                .map(|options| board(options.unwrap())),
+            Ok((30, 16, 120))
+        );
+        assert_eq!(
+            run(&["--width", "45", "--height", "3"], &config)
+                .map(|options| board(options.unwrap())),
+            Ok((45, 3, 40))
+        );
+        assert!(matches!(run(&["--help"], &config), Ok(None)));
+
+        // a config with a board that can't be played falls back to beginner
+        let broken = Config {
+            width: 1000,
+            ..Config::default()
+        };
+        assert_eq!(
+            run(&[], &broken).map(|options| board(options.unwrap())),
+            Ok((9, 9, 10))
+        );
+    }
+
+    #[test]
+    fn bad_arguments_are_refused() {
+        let config = Config::default();
+        for args in [
+            &["--width"][..],
+            &["--width", "nine"],
+            &["--mines", "-1"],
+            &["--difficulty", "hard"],
+            &["--theme", "blue"],
+            &["--bogus"],
+            &["--width", "7"],
+            &["--width", "46"],
+            &["--height", "2"],
+            &["--height", "43"],
+            &["--mines", "73"],
+        ] {
+            assert!(run(args, &config).is_err(), "{args:?} was accepted");
+        }
+    }
+
+    #[test]
+    fn boards_keep_room_for_the_first_click() {
+        assert_eq!(check_board(MIN_SIZE.0, MIN_SIZE.1, 15), Ok(()));
+        assert!(check_board(MIN_SIZE.0, MIN_SIZE.1, 16).is_err());
+        assert_eq!(check_board(MAX_SIZE.0, MAX_SIZE.1, 45 * 42 - 9), Ok(()));
+        assert!(check_board(MAX_SIZE.0 + 1, MAX_SIZE.1, 10).is_err());
+        assert!(check_board(MAX_SIZE.0, MAX_SIZE.1 + 1, 10).is_err());
+        assert!(check_board(MIN_SIZE.0 - 1, MIN_SIZE.1, 10).is_err());
+        assert!(check_board(MIN_SIZE.0, MIN_SIZE.1 - 1, 10).is_err());
+    }
+}
@@ -0,0 +1,191 @@
+//! Preferences kept between runs, stored as `config.toml` in [`config_dir`]
+//!
+//! Only flat `key = value` lines are understood, which is all the settings need:
+//!
+//! ```toml
+//! theme = "dark"
+//! width = 30
+//! height = 16
+//! mines = 99
+//! no_guess = false
+//! question_marks = true
+//! click_to_chord = false
+//! pack = "pixel"
+//! ```
+//!
+//! Missing keys keep their defaults and lines that can't be read are skipped, so an old or hand edited file
+//! never stops the game from starting.
+
+use crate::paths::{self, config_dir};
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Theme {
+    Dark,
+    Light,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Config {
+    /// `None` until one is picked, until then every launch picks one at random
+    pub theme: Option<Theme>,
+    /// The board last played on
+    pub width: usize,
+    pub height: usize,
+    pub mines: u32,
+    pub no_guess: bool,
+    /// Whether flags cycle on to a question mark
+    pub question_marks: bool,
+    /// Whether a left click on a revealed number chords it like the middle button
+    pub click_to_chord: bool,
+    /// The theme pack drawn over the built-in sprites, a folder in `themes`
+    pub pack: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Config {
+        Config {
+            theme: None,
+            width: 9,
+            height: 9,
+            mines: 10,
+            no_guess: false,
+            question_marks: false,
+            click_to_chord: false,
+            pack: None,
+        }
+    }
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join("config.toml"))
+    }
+
+    /// Reads the config file, using the defaults for anything it doesn't set
+    pub fn load() -> Config {
+        Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+
+    pub fn parse(text: &str) -> Config {
+        let mut config = Config::default();
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or_default();
+            let Some((key, value)) = line.split_once('=') else {
+                continue;
+            };
+            let value = value.trim();
+            match key.trim() {
+                "theme" => match value.trim_matches('"') {
+                    "dark" => config.theme = Some(Theme::Dark),
+                    "light" => config.theme = Some(Theme::Light),
+                    _ => {}
+                },
+                "width" => set(&mut config.width, value),
+                "height" => set(&mut config.height, value),
+                "mines" => set(&mut config.mines, value),
+                "no_guess" => set(&mut config.no_guess, value),
+                "question_marks" => set(&mut config.question_marks, value),
+                "click_to_chord" => set(&mut config.click_to_chord, value),
+                "pack" => {
+                    config.pack =
+                        Some(value.trim_matches('"').to_owned()).filter(|pack| !pack.is_empty())
+                }
+                _ => {}
+            }
+        }
+        config
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        paths::write("config.toml", self.to_string())
+    }
+}
+
+/// Leaves `field` alone if `value` doesn't parse
+fn set<T: std::str::FromStr>(field: &mut T, value: &str) {
+    if let Ok(value) = value.parse() {
+        *field = value;
+    }
+}
+
+impl std::fmt::Display for Config {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "# minesweeper-rs settings, anything missing goes back to its default"
+        )?;
+        match self.theme {
+            Some(Theme::Dark) => writeln!(f, "theme = \"dark\"")?,
+            Some(Theme::Light) => writeln!(f, "theme = \"light\"")?,
+            None => {}
+        }
+        writeln!(f, "width = {}", self.width)?;
+        writeln!(f, "height = {}", self.height)?;
+        writeln!(f, "mines = {}", self.mines)?;
+        writeln!(f, "no_guess = {}", self.no_guess)?;
+        writeln!(f, "question_marks = {}", self.question_marks)?;
+        writeln!(f, "click_to_chord = {}", self.click_to_chord)?;
+        match &self.pack {
+            Some(pack) => writeln!(f, "pack = \"{pack}\""),
+            None => Ok(()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_or_broken_settings_keep_their_defaults() {
+        assert_eq!(Config::parse(""), Config::default());
+        assert_eq!(
+            Config::parse("width = 30\nmines = 99 # expert\n"),
+            Config {
+                width: 30,
+                mines: 99,
+                ..Config::default()
+            }
+        );
+        assert_eq!(
+            Config::parse("width = wide\nheight 16\nmines = -1\nno_guess = yes\ntheme = \"blue\"\npack = \"\"\n[section]"),
+            Config::default()
+        );
+        assert_eq!(
+            Config::parse(
+                "theme = \"light\"\nunknown = 1\nquestion_marks = true\npack = \"pixel\""
+            ),
+            Config {
+                theme: Some(Theme::Light),
+                question_marks: true,
+                pack: Some("pixel".to_owned()),
+                ..Config::default()
+            }
+        );
+    }
+
+    #[test]
+    fn saved_configs_read_back_the_same() {
+        let config = Config {
+            theme: Some(Theme::Dark),
+            width: 45,
+            height: 42,
+            mines: 500,
+            no_guess: true,
+            question_marks: true,
+            click_to_chord: true,
+            pack: Some("pixel".to_owned()),
+        };
+        assert_eq!(Config::parse(&config.to_string()), config);
+        assert_eq!(
+            Config::parse(&Config::default().to_string()),
+            Config::default()
+        );
+    }
+}
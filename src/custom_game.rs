@@ -0,0 +1,198 @@
+use crate::cli::check_board;
+use minesweeper_rs::Data;
+
+pub const WIDTH: usize = 0;
+pub const HEIGHT: usize = 1;
+pub const MINES: usize = 2;
+pub const DENSITY: usize = 3;
+
+/// Digits each field takes, enough for the largest board
+const LENGTHS: [usize; 4] = [2, 2, 4, 2];
+
+/// The fields of the custom game panel as they're being typed, mines and density follow each other
+pub struct CustomGame {
+    pub fields: [String; 4],
+    pub selected: usize,
+}
+
+impl CustomGame {
+    pub fn new(data: &Data) -> CustomGame {
+        let mut custom = CustomGame {
+            fields: [
+                data.width().to_string(),
+                data.height().to_string(),
+                data.starting_mines().to_string(),
+                String::new(),
+            ],
+            selected: WIDTH,
+        };
+        custom.sync();
+        custom
+    }
+
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1) % 4;
+    }
+
+    pub fn select_previous(&mut self) {
+        self.selected = (self.selected + 3) % 4;
+    }
+
+    pub fn type_digit(&mut self, digit: u8) {
+        let field = &mut self.fields[self.selected];
+        if field == "0" {
+            field.clear();
+        }
+        if field.len() < LENGTHS[self.selected] {
+            field.push((b'0' + digit) as char);
+        }
+        self.sync();
+    }
+
+    pub fn backspace(&mut self) {
+        self.fields[self.selected].pop();
+        self.sync();
+    }
+
+    /// Width, height and mines if they make a board the game allows
+    pub fn board(&self) -> Option<(usize, usize, u32)> {
+        let (width, height, mines) = self.numbers()?;
+        let mines = u32::try_from(mines).ok()?;
+        check_board(width, height, mines).ok()?;
+        Some((width, height, mines))
+    }
+
+    /// Fields that keep the board from being started, shown faded
+    pub fn invalid(&self) -> [bool; 4] {
+        if self.board().is_some() {
+            return [false; 4];
+        }
+        let parse = |field: usize| self.fields[field].parse::<usize>().ok();
+        let width = parse(WIDTH).filter(|&width| check_board(width, 9, 0).is_ok());
+        let height = parse(HEIGHT).filter(|&height| check_board(9, height, 0).is_ok());
+        // with a valid size whatever's left wrong is the mine count
+        let mines = width.is_some() && height.is_some();
+        [width.is_none(), height.is_none(), mines, mines]
+    }
+
+    fn numbers(&self) -> Option<(usize, usize, usize)> {
+        Some((
+            self.fields[WIDTH].parse().ok()?,
+            self.fields[HEIGHT].parse().ok()?,
+            self.fields[MINES].parse().ok()?,
+        ))
+    }
+
+    /// Updates whichever of mines and density isn't being typed to match the other
+    fn sync(&mut self) {
+        let (Ok(width), Ok(height)) = (
+            self.fields[WIDTH].parse::<usize>(),
+            self.fields[HEIGHT].parse::<usize>(),
+        ) else {
+            return;
+        };
+        let tiles = width * height;
+        if tiles == 0 {
+            return;
+        }
+        if self.selected == DENSITY {
+            if let Ok(density) = self.fields[DENSITY].parse::<usize>() {
+                self.fields[MINES] = ((tiles * density + 50) / 100).to_string();
+            }
+        } else if let Ok(mines) = self.fields[MINES].parse::<usize>() {
+            self.fields[DENSITY] = ((mines * 100 + tiles / 2) / tiles).min(99).to_string();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn custom(width: &str, height: &str, mines: &str) -> CustomGame {
+        let mut custom = CustomGame::new(&Data::new(10, 9, 9));
+        custom.fields = [
+            width.to_owned(),
+            height.to_owned(),
+            mines.to_owned(),
+            String::new(),
+        ];
+        custom.selected = MINES;
+        custom.sync();
+        custom
+    }
+
+    fn type_in(custom: &mut CustomGame, field: usize, text: &str) {
+        custom.selected = field;
+        while !custom.fields[field].is_empty() {
+            custom.backspace();
+        }
+        text.bytes()
+            .for_each(|digit| custom.type_digit(digit - b'0'));
+    }
+
+    #[test]
+    fn mines_and_density_follow_each_other() {
+        let mut custom = CustomGame::new(&Data::new(10, 9, 9));
+        // 12.3% rounds down
+        assert_eq!(custom.fields[DENSITY], "12");
+
+        type_in(&mut custom, DENSITY, "20");
+        // 16.2 mines rounds down too
+        assert_eq!(custom.fields[MINES], "16");
+        type_in(&mut custom, DENSITY, "25");
+        // and 20.25 rounds to the nearest
+        assert_eq!(custom.fields[MINES], "20");
+
+        type_in(&mut custom, MINES, "41");
+        // 50.6% rounds up
+        assert_eq!(custom.fields[DENSITY], "51");
+        // a board that's all mines still shows a two digit density
+        type_in(&mut custom, MINES, "81");
+        assert_eq!(custom.fields[DENSITY], "99");
+
+        // nothing to work it out from without a size
+        type_in(&mut custom, WIDTH, "");
+        type_in(&mut custom, MINES, "5");
+        assert_eq!(custom.fields[DENSITY], "99");
+    }
+
+    #[test]
+    fn typing_replaces_a_lone_zero_and_stops_at_the_field_length() {
+        let mut custom = custom("0", "9", "10");
+        custom.selected = WIDTH;
+        custom.type_digit(9);
+        assert_eq!(custom.fields[WIDTH], "9");
+        custom.type_digit(0);
+        custom.type_digit(1);
+        assert_eq!(custom.fields[WIDTH], "90");
+
+        type_in(&mut custom, MINES, "123456");
+        assert_eq!(custom.fields[MINES], "1234");
+        type_in(&mut custom, DENSITY, "100");
+        assert_eq!(custom.fields[DENSITY], "10");
+    }
+
+    #[test]
+    fn only_boards_the_game_allows_can_be_started() {
+        assert_eq!(custom("9", "9", "10").board(), Some((9, 9, 10)));
+        assert_eq!(custom("45", "42", "1881").board(), Some((45, 42, 1881)));
+        assert_eq!(custom("9", "9", "72").board(), Some((9, 9, 72)));
+        assert_eq!(custom("9", "9", "73").board(), None);
+        assert_eq!(custom("7", "9", "10").board(), None);
+        assert_eq!(custom("9", "", "10").board(), None);
+
+        assert_eq!(custom("9", "9", "10").invalid(), [false; 4]);
+        assert_eq!(
+            custom("46", "9", "10").invalid(),
+            [true, false, false, false]
+        );
+        assert_eq!(
+            custom("9", "2", "10").invalid(),
+            [false, true, false, false]
+        );
+        assert_eq!(custom("", "", "10").invalid(), [true, true, false, false]);
+        assert_eq!(custom("9", "9", "73").invalid(), [false, false, true, true]);
+        assert_eq!(custom("9", "9", "").invalid(), [false, false, true, true]);
+    }
+}
@@ -0,0 +1,1318 @@
+use crate::save::SavedGame;
+use crate::solver::Solver;
+use crate::tile::{Tile, TileContent, TileState};
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum GameStatus {
+    /// No tile has been revealed yet, mines get placed on the first reveal
+    Ready,
+    Playing,
+    Won,
+    Lost,
+}
+
+impl GameStatus {
+    #[inline]
+    pub fn is_finished(self) -> bool {
+        matches!(self, GameStatus::Won | GameStatus::Lost)
+    }
+}
+
+pub struct Data {
+    tiles_left: u32,
+    placed_mines: bool,
+    mines: i64, // this has to be signed, and wide enough for every tile to be flagged
+    starting_mines: u32,
+    death_pos: Option<(usize, usize)>,
+    start_time: Instant,
+    board: Vec<Tile>,
+    width: usize,
+    height: usize,
+    /// Milliseconds the game took, once it's over
+    finish_time: Option<u64>,
+    seed: u64,
+    no_guess: bool,
+    /// Where the mines were placed around, a retry starts from here
+    first_click: Option<(usize, usize)>,
+    /// Whether this is a retry of a board whose layout the player has already seen
+    retried: bool,
+    /// Moves that can be undone, most recent last and at most [`UNDO_LIMIT`] of them
+    history: VecDeque<Change>,
+    /// Undone moves that can be made again, cleared by any new move
+    redo: Vec<Change>,
+    used_undo: bool,
+    /// Whether flagging a flagged tile gives it a question mark instead of clearing it
+    question_marks: bool,
+    /// Whether no guess mode ran out of repairs and placed mines that may need a guess
+    no_guess_failed: bool,
+    /// Tiles changed so far by the move being made, `None` outside of a tracked move
+    changes: Option<Vec<(usize, Tile, Tile)>>,
+}
+
+/// Moves kept in the undo history, older ones are forgotten
+const UNDO_LIMIT: usize = 1000;
+
+/// Everything about a game besides its tiles
+#[derive(Copy, Clone, PartialEq, Eq)]
+struct Counters {
+    tiles_left: u32,
+    placed_mines: bool,
+    mines: i64,
+    death_pos: Option<(usize, usize)>,
+    finish_time: Option<u64>,
+    first_click: Option<(usize, usize)>,
+}
+
+/// What a single reveal, flag or chord did, enough to take it back or make it again
+struct Change {
+    /// Index, before and after of every tile in the order it changed, the whole flood fill of a reveal
+    tiles: Vec<(usize, Tile, Tile)>,
+    before: Counters,
+    after: Counters,
+}
+
+/// Seconds padded to `digits` characters, keeping as many decimals as fit and returning the index of the
+/// digit the decimal point follows
+pub fn time_digits(millis: u64, digits: usize) -> (String, Option<usize>) {
+    let seconds = millis / 1000;
+    let whole = seconds.to_string().len();
+    if whole >= digits {
+        return (
+            format!("{:>digits$}", seconds.min(10u64.pow(digits as u32) - 1)),
+            None,
+        );
+    }
+    let decimals = (digits - whole).min(3);
+    let scaled = millis / 10u64.pow(3 - decimals as u32);
+    (
+        format!("{:>digits$}", format!("{:0>1$}", scaled, whole + decimals)),
+        Some(digits - decimals - 1),
+    )
+}
+
+/// Tiles the solver may replay while repairing a no guess layout, spread over as many repairs as the board
+/// allows before settling for one that needs a guess
+///
+/// Counted in work rather than time so the same seed always gives the same board. Expert takes a few repairs
+/// and stays in the milliseconds, the densest boards give up within about half a second.
+const NO_GUESS_BUDGET: usize = 60_000;
+
+/// Everything that decides what board a game is played on
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct GameConfig {
+    pub width: usize,
+    pub height: usize,
+    /// Capped so the first click's 3x3 square always has room
+    pub mines: u32,
+    /// Picked at random when `None`
+    pub seed: Option<u64>,
+    pub no_guess: bool,
+}
+
+impl GameConfig {
+    pub const BEGINNER: GameConfig = GameConfig::new(9, 9, 10);
+    pub const INTERMEDIATE: GameConfig = GameConfig::new(16, 16, 40);
+    pub const EXPERT: GameConfig = GameConfig::new(30, 16, 99);
+
+    /// A random board without no guess mode
+    pub const fn new(width: usize, height: usize, mines: u32) -> GameConfig {
+        GameConfig {
+            width,
+            height,
+            mines,
+            seed: None,
+            no_guess: false,
+        }
+    }
+
+    /// The number of tiles on the board, `None` if there are more than the counters can hold
+    pub fn tile_count(&self) -> Option<u32> {
+        u32::try_from(self.width.checked_mul(self.height)?).ok()
+    }
+}
+
+impl Data {
+    pub fn new(mines: u32, width: usize, height: usize) -> Data {
+        Self::from_config(GameConfig::new(width, height, mines))
+    }
+
+    /// A board whose layout only depends on `seed` and the first tile revealed
+    pub fn with_seed(mines: u32, width: usize, height: usize, seed: u64) -> Data {
+        Self::from_config(GameConfig {
+            seed: Some(seed),
+            ..GameConfig::new(width, height, mines)
+        })
+    }
+
+    pub fn from_config(config: GameConfig) -> Data {
+        let mut data = Data {
+            width: 0,
+            height: 0,
+            tiles_left: 0,
+            placed_mines: false,
+            mines: 0,
+            starting_mines: 0,
+            death_pos: None,
+            finish_time: None,
+            start_time: Instant::now(),
+            board: Vec::new(),
+            seed: 0,
+            no_guess: false,
+            first_click: None,
+            retried: false,
+            history: VecDeque::new(),
+            redo: Vec::new(),
+            used_undo: false,
+            question_marks: false,
+            no_guess_failed: false,
+            changes: None,
+        };
+        data.new_game(config);
+        data
+    }
+
+    pub(crate) fn from_saved(saved: SavedGame) -> Data {
+        let now = Instant::now();
+        Data {
+            width: saved.width,
+            height: saved.height,
+            tiles_left: saved.tiles_left,
+            placed_mines: saved.placed_mines,
+            mines: saved.mines,
+            starting_mines: saved.starting_mines,
+            death_pos: None,
+            finish_time: None,
+            start_time: now
+                .checked_sub(Duration::from_millis(saved.elapsed))
+                .unwrap_or(now),
+            board: saved.board,
+            seed: saved.seed,
+            no_guess: saved.no_guess,
+            first_click: None,
+            retried: false,
+            history: VecDeque::new(),
+            redo: Vec::new(),
+            // the undo history isn't saved, but whether it was used is
+            used_undo: saved.practice,
+            question_marks: false,
+            no_guess_failed: false,
+            changes: None,
+        }
+    }
+
+    #[inline(always)]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    #[inline(always)]
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    /// Mines left according to the flags placed, can go negative
+    #[inline(always)]
+    pub fn mines(&self) -> i64 {
+        self.mines
+    }
+
+    #[inline(always)]
+    pub fn starting_mines(&self) -> u32 {
+        self.starting_mines
+    }
+
+    #[inline(always)]
+    pub fn tiles_left(&self) -> u32 {
+        self.tiles_left
+    }
+
+    #[inline(always)]
+    pub fn placed_mines(&self) -> bool {
+        self.placed_mines
+    }
+
+    #[inline(always)]
+    pub fn death_pos(&self) -> Option<(usize, usize)> {
+        self.death_pos
+    }
+
+    /// When the first tile was revealed
+    #[inline(always)]
+    pub fn start_time(&self) -> Instant {
+        self.start_time
+    }
+
+    /// Milliseconds the finished game took
+    #[inline(always)]
+    pub fn finish_time(&self) -> Option<u64> {
+        self.finish_time
+    }
+
+    #[inline(always)]
+    pub fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// The tile the mines were placed around, `None` before the first reveal and for resumed games
+    #[inline(always)]
+    pub fn first_click(&self) -> Option<(usize, usize)> {
+        self.first_click
+    }
+
+    /// Whether this game is a [`retry`](Data::retry)
+    #[inline(always)]
+    pub fn retried(&self) -> bool {
+        self.retried
+    }
+
+    /// Whether a move has been undone since the game started, which doesn't count towards stats
+    #[inline(always)]
+    pub fn used_undo(&self) -> bool {
+        self.used_undo
+    }
+
+    /// Whether a finished game should count towards stats, practice with retries and undo doesn't
+    #[inline(always)]
+    pub fn counts_for_records(&self) -> bool {
+        !self.retried && !self.used_undo
+    }
+
+    #[inline(always)]
+    pub fn question_marks(&self) -> bool {
+        self.question_marks
+    }
+
+    /// Kept across new games, like any other input preference
+    #[inline(always)]
+    pub fn set_question_marks(&mut self, question_marks: bool) {
+        self.question_marks = question_marks;
+    }
+
+    /// Whether mines get placed so the board can be cleared by logic alone
+    #[inline(always)]
+    pub fn no_guess(&self) -> bool {
+        self.no_guess
+    }
+
+    /// Takes effect the next time mines are placed
+    #[inline(always)]
+    pub fn set_no_guess(&mut self, no_guess: bool) {
+        self.no_guess = no_guess;
+    }
+
+    /// Whether no guess mode couldn't make this board solvable without guessing and left it as it was,
+    /// only known once the mines are placed and not kept by saves
+    #[inline(always)]
+    pub fn no_guess_failed(&self) -> bool {
+        self.no_guess_failed
+    }
+
+    pub fn status(&self) -> GameStatus {
+        if self.death_pos.is_some() {
+            GameStatus::Lost
+        } else if self.tiles_left == 0 {
+            GameStatus::Won
+        } else if self.placed_mines {
+            GameStatus::Playing
+        } else {
+            GameStatus::Ready
+        }
+    }
+
+    /// Milliseconds on the clock, stops once the game is finished
+    pub fn elapsed(&self) -> u64 {
+        if !self.placed_mines {
+            0
+        } else if let Some(time) = self.finish_time {
+            time
+        } else {
+            self.start_time.elapsed().as_millis() as u64
+        }
+    }
+
+    /// The mine counter above the board, three digits that stop at -99 and 999
+    pub fn mine_counter(&self) -> String {
+        format!("{:>3}", self.mines.clamp(-99, 999))
+    }
+
+    /// The clock above the board, whole seconds while playing and as many decimals as fit once it's over
+    pub fn clock(&self) -> (String, Option<usize>) {
+        Data::clock_digits(self.elapsed(), self.finish_time.is_some())
+    }
+
+    /// The clock as [`clock`](Data::clock) shows it `millis` into a game, for a frontend that reads the time
+    /// once per frame
+    pub fn clock_digits(millis: u64, finished: bool) -> (String, Option<usize>) {
+        if finished {
+            time_digits(millis, 3)
+        } else {
+            (format!("{:>3}", (millis / 1000).min(999)), None)
+        }
+    }
+
+    #[inline(always)]
+    pub fn in_bounds(&self, x: usize, y: usize) -> bool {
+        x < self.width && y < self.height
+    }
+
+    /// Panics if `(x, y)` is off the board
+    #[inline(always)]
+    pub fn get(&self, x: usize, y: usize) -> Tile {
+        self.board[self.index(x, y)]
+    }
+
+    #[inline(always)]
+    pub(crate) fn get_mut(&mut self, x: usize, y: usize) -> &mut Tile {
+        let index = self.index(x, y);
+        &mut self.board[index]
+    }
+
+    /// Checks both coordinates, a column past the edge would otherwise land on the next row
+    #[inline(always)]
+    fn index(&self, x: usize, y: usize) -> usize {
+        assert!(
+            self.in_bounds(x, y),
+            "({x}, {y}) is off the {}x{} board",
+            self.width,
+            self.height
+        );
+        y * self.width + x
+    }
+
+    /// The settings the current game was started with, including its seed
+    pub fn config(&self) -> GameConfig {
+        GameConfig {
+            width: self.width,
+            height: self.height,
+            mines: self.starting_mines,
+            seed: Some(self.seed),
+            no_guess: self.no_guess,
+        }
+    }
+
+    /// Throws away the current game for a new one, every way of starting a game goes through here
+    ///
+    /// Panics if the board has more than `u32::MAX` tiles, see [`GameConfig::tile_count`].
+    pub fn new_game(&mut self, config: GameConfig) {
+        debug_assert!(config.width > 0 && config.height > 0);
+        let tiles = config
+            .tile_count()
+            .expect("board has more tiles than the counters can hold");
+        let mines = config.mines.min(tiles.saturating_sub(9));
+        self.width = config.width;
+        self.height = config.height;
+        self.starting_mines = mines;
+        self.mines = mines as i64;
+        self.tiles_left = tiles - mines;
+        self.seed = config.seed.unwrap_or_else(rand::random);
+        self.no_guess = config.no_guess;
+        self.first_click = None;
+        self.retried = false;
+        self.history.clear();
+        self.redo.clear();
+        self.used_undo = false;
+        self.no_guess_failed = false;
+        self.placed_mines = false;
+        self.death_pos = None;
+        self.finish_time = None;
+        self.start_time = Instant::now();
+        self.board.clear();
+        self.board.resize(tiles as usize, Tile::EMPTY);
+    }
+
+    /// Starts a fresh game on the same board size and mine count with a new seed
+    pub fn restart(&mut self) {
+        self.new_game(GameConfig {
+            seed: None,
+            ..self.config()
+        });
+    }
+
+    /// Plays the same board again from its first click, keeping the mines where they are
+    ///
+    /// Does nothing before the first click, when there's no layout to keep yet.
+    pub fn retry(&mut self) {
+        let Some((x, y)) = self.first_click else {
+            return;
+        };
+        self.board
+            .iter_mut()
+            .for_each(|tile| tile.set_state(TileState::Hidden));
+        self.mines = self.starting_mines as i64;
+        self.tiles_left = self.board.len() as u32 - self.starting_mines;
+        self.death_pos = None;
+        self.finish_time = None;
+        self.start_time = Instant::now();
+        self.retried = true;
+        self.history.clear();
+        self.redo.clear();
+        self.click(x, y);
+    }
+
+    /// The board with every safe tile revealed and every mine flagged, `None` before the mines are placed
+    ///
+    /// A lost game stays lost, so the mine that ended it still stands out.
+    pub fn solution(&self) -> Option<Data> {
+        if !self.placed_mines {
+            return None;
+        }
+        let board = self
+            .board
+            .iter()
+            .copied()
+            .map(|mut tile| {
+                tile.set_state(if tile.is_mine() {
+                    TileState::Flagged
+                } else {
+                    TileState::Revealed
+                });
+                tile
+            })
+            .collect();
+        Some(Data {
+            board,
+            tiles_left: 0,
+            mines: 0,
+            history: VecDeque::new(),
+            redo: Vec::new(),
+            changes: None,
+            ..*self
+        })
+    }
+
+    /// Only for a board without mines yet, the neighbour counts add up on top of the old ones otherwise
+    pub(crate) fn place_mines(&mut self, avoid_x: usize, avoid_y: usize) {
+        // ChaCha8 rather than StdRng, which is allowed to change between rand versions
+        let mut rand = ChaCha8Rng::seed_from_u64(self.seed);
+        self.board.iter_mut().for_each(Tile::clear_content);
+        let mut i = 0;
+        while i < self.starting_mines {
+            let x = rand.gen_range(0..self.width);
+            let y = rand.gen_range(0..self.height);
+            if x.wrapping_sub(avoid_x).wrapping_add(1) <= 2
+                && y.wrapping_sub(avoid_y).wrapping_add(1) <= 2
+            {
+                continue;
+            }
+
+            if self.get(x, y).is_mine() {
+                continue;
+            }
+
+            self.add_mine(x, y);
+            i += 1
+        }
+
+        self.no_guess_failed = self.no_guess && !self.repair(avoid_x, avoid_y, &mut rand);
+    }
+
+    /// Moves mines out of wherever the solver gets stuck until the board clears from `(x, y)` by logic alone,
+    /// returns whether it does within [`NO_GUESS_BUDGET`]
+    ///
+    /// One mine the numbers couldn't place moves to a tile no number sees yet, or once there are none of those
+    /// to anywhere already settled, which the next replay works out afresh. The first click's square is kept
+    /// clear either way.
+    fn repair(&mut self, x: usize, y: usize, rand: &mut ChaCha8Rng) -> bool {
+        for _ in 0..(NO_GUESS_BUDGET / self.board.len()).max(1) {
+            let Some(solver) = Solver::play(self, x, y) else {
+                return false;
+            };
+            let (frontier, rest): (Vec<_>, Vec<_>) = solver
+                .undecided()
+                .partition(|&(x, y)| solver.next_to_number(x, y));
+            if frontier.is_empty() && rest.is_empty() {
+                return true;
+            }
+            let mines = |tiles: &[(usize, usize)]| {
+                tiles
+                    .iter()
+                    .copied()
+                    .filter(|&(x, y)| self.get(x, y).is_mine())
+                    .collect::<Vec<_>>()
+            };
+            let mut from = mines(&frontier);
+            if from.is_empty() {
+                from = mines(&rest);
+            }
+            let mut to = rest
+                .iter()
+                .copied()
+                .filter(|&(x, y)| !self.get(x, y).is_mine())
+                .collect::<Vec<_>>();
+            if to.is_empty() {
+                to = (0..self.height)
+                    .flat_map(|y| (0..self.width).map(move |x| (x, y)))
+                    .filter(|&(to_x, to_y)| {
+                        !self.get(to_x, to_y).is_mine()
+                            && !frontier.contains(&(to_x, to_y))
+                            && (to_x.abs_diff(x) > 1 || to_y.abs_diff(y) > 1)
+                    })
+                    .collect();
+            }
+            // the solver settles whatever is all mines or all safe, so there's always both
+            if from.is_empty() || to.is_empty() {
+                return false;
+            }
+            let from = from[rand.gen_range(0..from.len())];
+            let to = to[rand.gen_range(0..to.len())];
+            self.remove_mine(from.0, from.1);
+            self.add_mine(to.0, to.1);
+        }
+        Solver::is_solvable(self, x, y)
+    }
+
+    fn add_mine(&mut self, x: usize, y: usize) {
+        self.get_mut(x, y).set_mine();
+        for (x, y) in self.neighbours(x, y) {
+            self.get_mut(x, y).add_neighbouring_mine();
+        }
+    }
+
+    fn remove_mine(&mut self, x: usize, y: usize) {
+        self.get_mut(x, y).clear_mine();
+        for (x, y) in self.neighbours(x, y) {
+            self.get_mut(x, y).remove_neighbouring_mine();
+        }
+    }
+
+    /// Reveals a tile, placing the mines around it first if this is the first reveal of the game
+    pub fn reveal(&mut self, x: usize, y: usize) {
+        self.tracked(|data| data.click(x, y));
+    }
+
+    /// Reveals the unflagged neighbours of a revealed number once that many of them are flagged
+    pub fn chord(&mut self, x: usize, y: usize) {
+        self.tracked(|data| data.chord_untracked(x, y));
+    }
+
+    fn chord_untracked(&mut self, x: usize, y: usize) {
+        if !self.can_chord(x, y) {
+            return;
+        }
+
+        for (x, y) in self.neighbours(x, y) {
+            if !self.status().is_finished() {
+                self.click(x, y);
+            }
+        }
+    }
+
+    /// Whether chording the tile would reveal anything, flags that are wrong still count
+    pub fn can_chord(&self, x: usize, y: usize) -> bool {
+        if !self.in_bounds(x, y) || self.status().is_finished() {
+            return false;
+        }
+        let tile = self.get(x, y);
+        let TileContent::Number(count) = tile.content() else {
+            return false;
+        };
+        let flags = self
+            .neighbours(x, y)
+            .filter(|&(x, y)| self.get(x, y).is_flagged())
+            .count();
+        tile.is_revealed()
+            && flags == count as usize
+            && self.neighbours(x, y).any(|(x, y)| {
+                !matches!(
+                    self.get(x, y).state(),
+                    TileState::Revealed | TileState::Flagged
+                )
+            })
+    }
+
+    /// Like [`reveal`](Data::reveal) but left out of the undo history, tiles off the board are ignored
+    pub fn click(&mut self, x: usize, y: usize) {
+        if !self.in_bounds(x, y) || self.status().is_finished() {
+            return;
+        }
+        if !self.placed_mines {
+            self.placed_mines = true;
+            self.start_time = Instant::now();
+            self.first_click = Some((x, y));
+            // placing the mines changes tiles all over the board, the one move worth copying it for
+            let board = self.changes.is_some().then(|| self.board.clone());
+            self.place_mines(x, y);
+            if let (Some(board), Some(changes)) = (board, &mut self.changes) {
+                changes.extend(
+                    board
+                        .into_iter()
+                        .zip(&self.board)
+                        .enumerate()
+                        .filter(|(_, (old, new))| old != *new)
+                        .map(|(index, (old, &new))| (index, old, new)),
+                );
+            }
+        }
+
+        let tile = self.get(x, y);
+        if matches!(tile.state(), TileState::Revealed | TileState::Flagged) {
+            return;
+        }
+
+        match tile.content() {
+            TileContent::Mine => {
+                self.death_pos = Some((x, y));
+                self.finish_time = Some(self.start_time.elapsed().as_millis() as u64);
+            }
+            TileContent::Number(count) => {
+                self.set_state(x, y, TileState::Revealed);
+                self.tiles_left -= 1;
+                if count == 0 {
+                    self.flood_fill(x, y);
+                }
+            }
+        }
+
+        if self.tiles_left == 0 {
+            self.finish_time = Some(self.start_time.elapsed().as_millis() as u64);
+        }
+    }
+
+    /// Reveals everything connected to an empty tile, keeping a list of the empty tiles still to spread from
+    /// rather than recursing so a huge open board can't overflow the stack
+    fn flood_fill(&mut self, x: usize, y: usize) {
+        let mut empty = vec![(x, y)];
+        while let Some((x, y)) = empty.pop() {
+            for (x, y) in self.neighbours(x, y) {
+                let tile = self.get(x, y);
+                // next to an empty tile everything is safe, and revealing it now means it's only pushed once
+                if matches!(tile.state(), TileState::Revealed | TileState::Flagged) {
+                    continue;
+                }
+                self.set_state(x, y, TileState::Revealed);
+                self.tiles_left -= 1;
+                if tile.neighbouring_mines() == 0 {
+                    empty.push((x, y));
+                }
+            }
+        }
+    }
+
+    /// Cycles a hidden tile through flagged and, if they're enabled, question marked
+    ///
+    /// Question marks are only a note for the player, they don't count as a mine.
+    pub fn flag(&mut self, x: usize, y: usize) {
+        self.tracked(|data| data.flag_untracked(x, y));
+    }
+
+    fn flag_untracked(&mut self, x: usize, y: usize) {
+        if !self.in_bounds(x, y) || self.status().is_finished() {
+            return;
+        }
+
+        match self.get(x, y).state() {
+            TileState::Hidden => {
+                self.mines -= 1;
+                self.set_state(x, y, TileState::Flagged);
+            }
+            TileState::Flagged => {
+                self.mines += 1;
+                let next = if self.question_marks {
+                    TileState::Question
+                } else {
+                    TileState::Hidden
+                };
+                self.set_state(x, y, next);
+            }
+            TileState::Question => self.set_state(x, y, TileState::Hidden),
+            TileState::Revealed => {}
+        }
+    }
+
+    /// Changes a tile's state, noting it down for the undo history during a tracked move
+    fn set_state(&mut self, x: usize, y: usize, state: TileState) {
+        let index = self.index(x, y);
+        let old = self.board[index];
+        self.board[index].set_state(state);
+        if let Some(changes) = &mut self.changes {
+            changes.push((index, old, self.board[index]));
+        }
+    }
+
+    fn counters(&self) -> Counters {
+        Counters {
+            tiles_left: self.tiles_left,
+            placed_mines: self.placed_mines,
+            mines: self.mines,
+            death_pos: self.death_pos,
+            finish_time: self.finish_time,
+            first_click: self.first_click,
+        }
+    }
+
+    fn set_counters(&mut self, counters: Counters) {
+        self.tiles_left = counters.tiles_left;
+        self.placed_mines = counters.placed_mines;
+        self.mines = counters.mines;
+        self.death_pos = counters.death_pos;
+        self.finish_time = counters.finish_time;
+        self.first_click = counters.first_click;
+    }
+
+    /// Makes a move, keeping what it changed on the undo stack
+    fn tracked(&mut self, make_move: impl FnOnce(&mut Data)) {
+        let before = self.counters();
+        self.changes = Some(Vec::new());
+        make_move(self);
+        let tiles = self.changes.take().unwrap_or_default();
+        let after = self.counters();
+        if tiles.is_empty() && before == after {
+            return;
+        }
+        if self.history.len() == UNDO_LIMIT {
+            self.history.pop_front();
+        }
+        self.history.push_back(Change {
+            tiles,
+            before,
+            after,
+        });
+        self.redo.clear();
+    }
+
+    #[inline]
+    pub fn can_undo(&self) -> bool {
+        !self.history.is_empty()
+    }
+
+    #[inline]
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Takes back the last move, including one that hit a mine, returning whether there was one
+    pub fn undo(&mut self) -> bool {
+        let Some(change) = self.history.pop_back() else {
+            return false;
+        };
+        for &(index, old, _) in change.tiles.iter().rev() {
+            self.board[index] = old;
+        }
+        self.set_counters(change.before);
+        self.used_undo = true;
+        self.redo.push(change);
+        true
+    }
+
+    /// Makes the last undone move again, returning whether there was one
+    pub fn redo(&mut self) -> bool {
+        let Some(change) = self.redo.pop() else {
+            return false;
+        };
+        for &(index, _, new) in &change.tiles {
+            self.board[index] = new;
+        }
+        self.set_counters(change.after);
+        self.history.push_back(change);
+        true
+    }
+
+    /// The in-bounds tiles surrounding `(x, y)`
+    pub fn neighbours(&self, x: usize, y: usize) -> impl Iterator<Item = (usize, usize)> {
+        let width = self.width;
+        let height = self.height;
+        [
+            (x.wrapping_sub(1), y.wrapping_sub(1)),
+            (x, y.wrapping_sub(1)),
+            (x + 1, y.wrapping_sub(1)),
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x.wrapping_sub(1), y + 1),
+            (x, y + 1),
+            (x + 1, y + 1),
+        ]
+        .into_iter()
+        .filter(move |&(x, y)| x < width && y < height)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Every tile, row by row, to compare games with
+    fn layout(data: &Data) -> Vec<Tile> {
+        (0..data.height())
+            .flat_map(|y| (0..data.width()).map(move |x| (x, y)))
+            .map(|(x, y)| data.get(x, y))
+            .collect()
+    }
+
+    /// Flags a corner and reveals the middle, then keeps clicking until the game is lost
+    fn play_until_lost(data: &mut Data) {
+        data.flag(0, 0);
+        data.reveal(data.width() / 2, data.height() / 2);
+        for y in 0..data.height() {
+            for x in 0..data.width() {
+                data.reveal(x, y);
+            }
+        }
+        assert_eq!(data.status(), GameStatus::Lost);
+    }
+
+    fn assert_fresh(data: &Data, config: GameConfig) {
+        assert_eq!(data.width(), config.width);
+        assert_eq!(data.height(), config.height);
+        assert_eq!(data.starting_mines(), config.mines);
+        assert_eq!(data.mines(), config.mines as i64);
+        assert_eq!(
+            data.tiles_left() as usize,
+            config.width * config.height - config.mines as usize
+        );
+        assert_eq!(data.status(), GameStatus::Ready);
+        assert_eq!(data.death_pos(), None);
+        assert_eq!(data.finish_time(), None);
+        assert_eq!(data.elapsed(), 0);
+        assert_eq!(data.no_guess(), config.no_guess);
+        assert!(layout(data).iter().all(|&tile| tile == Tile::EMPTY));
+    }
+
+    #[test]
+    fn constructors_start_fresh_games() {
+        assert_fresh(&Data::new(10, 9, 9), GameConfig::BEGINNER);
+        let data = Data::with_seed(40, 16, 16, 7);
+        assert_fresh(&data, GameConfig::INTERMEDIATE);
+        assert_eq!(data.seed(), 7);
+        let config = GameConfig {
+            seed: Some(3),
+            no_guess: true,
+            ..GameConfig::EXPERT
+        };
+        let data = Data::from_config(config);
+        assert_fresh(&data, config);
+        assert_eq!(data.config(), config);
+    }
+
+    #[test]
+    fn difficulty_change_resizes_the_board() {
+        let mut data = Data::from_config(GameConfig::EXPERT);
+        play_until_lost(&mut data);
+        for config in [
+            GameConfig::BEGINNER,
+            GameConfig::INTERMEDIATE,
+            GameConfig::EXPERT,
+        ] {
+            data.new_game(config);
+            assert_fresh(&data, config);
+            play_until_lost(&mut data);
+        }
+    }
+
+    #[test]
+    fn mine_count_change_keeps_the_size() {
+        let mut data = Data::from_config(GameConfig::BEGINNER);
+        data.flag(1, 1);
+        for mines in [11, 10, 0, 72] {
+            data.new_game(GameConfig {
+                mines,
+                seed: None,
+                ..data.config()
+            });
+            assert_fresh(&data, GameConfig::new(9, 9, mines));
+        }
+    }
+
+    #[test]
+    fn resize_keeps_the_mine_count_where_it_fits() {
+        let mut data = Data::from_config(GameConfig::EXPERT);
+        play_until_lost(&mut data);
+        data.new_game(GameConfig {
+            width: 20,
+            height: 10,
+            ..data.config()
+        });
+        assert_fresh(&data, GameConfig::new(20, 10, 99));
+        // shrinking past the mines caps them instead of leaving a board that can't be generated
+        data.new_game(GameConfig {
+            width: 8,
+            height: 3,
+            ..data.config()
+        });
+        assert_fresh(&data, GameConfig::new(8, 3, 15));
+        // the first click's 3x3 square is all that's left to clear
+        data.reveal(4, 1);
+        assert_eq!(data.status(), GameStatus::Won);
+    }
+
+    #[test]
+    fn restart_keeps_the_settings_with_a_new_seed() {
+        let config = GameConfig {
+            seed: Some(1),
+            ..GameConfig::INTERMEDIATE
+        };
+        let mut data = Data::from_config(config);
+        play_until_lost(&mut data);
+        data.restart();
+        assert_fresh(&data, config);
+        assert_ne!(data.seed(), 1);
+
+        // a won game restarts just the same
+        let mut data = Data::from_config(GameConfig::new(9, 9, 0));
+        data.reveal(0, 0);
+        assert_eq!(data.status(), GameStatus::Won);
+        data.restart();
+        assert_fresh(&data, GameConfig::new(9, 9, 0));
+    }
+
+    #[test]
+    fn retry_keeps_the_layout_and_first_click() {
+        let mut data = Data::from_config(GameConfig {
+            seed: Some(9),
+            ..GameConfig::INTERMEDIATE
+        });
+        data.retry();
+        assert_fresh(&data, GameConfig::INTERMEDIATE);
+        play_until_lost(&mut data);
+        let mines = layout(&data)
+            .iter()
+            .map(|tile| tile.is_mine())
+            .collect::<Vec<_>>();
+
+        data.retry();
+        assert!(data.retried());
+        assert_eq!(data.status(), GameStatus::Playing);
+        assert_eq!(data.first_click(), Some((8, 8)));
+        assert_eq!(data.mines(), 40);
+        assert_eq!(data.death_pos(), None);
+        assert_eq!(data.finish_time(), None);
+        assert!(data.get(8, 8).is_revealed());
+        let tiles = layout(&data);
+        assert_eq!(
+            tiles.iter().map(|tile| tile.is_mine()).collect::<Vec<_>>(),
+            mines
+        );
+        let revealed = tiles.iter().filter(|tile| tile.is_revealed()).count();
+        assert_eq!(data.tiles_left() as usize, 16 * 16 - 40 - revealed);
+        assert!(tiles.iter().all(|tile| !tile.is_flagged()));
+
+        // a new game forgets the layout
+        data.restart();
+        assert!(!data.retried());
+        assert_eq!(data.first_click(), None);
+    }
+
+    #[test]
+    fn undo_takes_back_a_fatal_click() {
+        let mut data = Data::from_config(GameConfig {
+            seed: Some(5),
+            ..GameConfig::INTERMEDIATE
+        });
+        data.reveal(8, 8);
+        data.flag(0, 0);
+        let (before, tiles_left) = (layout(&data), data.tiles_left());
+        let mine = (0..16 * 16)
+            .map(|index| (index % 16, index / 16))
+            .find(|&(x, y)| data.get(x, y).is_mine() && !data.get(x, y).is_flagged())
+            .unwrap();
+        data.reveal(mine.0, mine.1);
+        assert_eq!(data.status(), GameStatus::Lost);
+        assert!(data.counts_for_records());
+
+        assert!(data.undo());
+        assert_eq!(data.status(), GameStatus::Playing);
+        assert_eq!(data.finish_time(), None);
+        assert_eq!(layout(&data), before);
+        assert_eq!(data.tiles_left(), tiles_left);
+        assert!(!data.counts_for_records());
+
+        assert!(data.redo());
+        assert_eq!(data.death_pos(), Some(mine));
+        assert!(!data.redo());
+    }
+
+    #[test]
+    fn undo_and_redo_walk_the_history() {
+        let mut data = Data::from_config(GameConfig {
+            seed: Some(5),
+            ..GameConfig::INTERMEDIATE
+        });
+        let fresh = layout(&data);
+        data.reveal(8, 8);
+        let opened = layout(&data);
+        // a flood fill is a single move
+        assert!(opened.iter().filter(|tile| tile.is_revealed()).count() > 1);
+        data.flag(0, 0);
+        data.flag(0, 0);
+        // moves that change nothing aren't kept
+        data.reveal(8, 8);
+
+        assert!(data.undo());
+        assert!(data.get(0, 0).is_flagged());
+        assert!(data.undo());
+        assert_eq!(layout(&data), opened);
+        assert_eq!(data.mines(), 40);
+        assert!(data.undo());
+        assert_eq!(layout(&data), fresh);
+        assert_eq!(data.status(), GameStatus::Ready);
+        assert!(!data.undo());
+
+        assert!(data.redo());
+        assert_eq!(layout(&data), opened);
+        assert_eq!(data.status(), GameStatus::Playing);
+        // a new move drops what was undone
+        data.flag(1, 0);
+        assert!(!data.can_redo());
+        data.restart();
+        assert!(!data.can_undo());
+        assert!(!data.used_undo());
+    }
+
+    #[test]
+    fn time_digits_keep_as_many_decimals_as_fit() {
+        assert_eq!(time_digits(500, 3), ("050".to_owned(), Some(0)));
+        assert_eq!(time_digits(12_345, 3), ("123".to_owned(), Some(1)));
+        assert_eq!(time_digits(999_999, 3), ("999".to_owned(), None));
+        assert_eq!(time_digits(5_000_000, 3), ("999".to_owned(), None));
+        assert_eq!(time_digits(12_345, 5), ("12345".to_owned(), Some(1)));
+        assert_eq!(time_digits(123_456, 5), ("12345".to_owned(), Some(2)));
+    }
+
+    #[test]
+    fn undo_history_forgets_the_oldest_moves() {
+        let mut data = Data::with_seed(10, 9, 9, 3);
+        data.reveal(4, 4);
+        let (x, y) = (0..81)
+            .map(|index| (index % 9, index / 9))
+            .find(|&(x, y)| !data.get(x, y).is_revealed())
+            .unwrap();
+        for _ in 0..UNDO_LIMIT + 1 {
+            data.flag(x, y);
+        }
+        let mut undone = 0;
+        while data.undo() {
+            undone += 1;
+        }
+        assert_eq!(undone, UNDO_LIMIT);
+        // the reveal was the one forgotten
+        assert_eq!(data.status(), GameStatus::Playing);
+        assert!(data.get(x, y).is_flagged());
+    }
+
+    /// A revealed number next to both mines and hidden safe tiles
+    fn chordable(data: &Data) -> (usize, usize) {
+        (0..data.height())
+            .flat_map(|y| (0..data.width()).map(move |x| (x, y)))
+            .find(|&(x, y)| {
+                let hidden_safe = data
+                    .neighbours(x, y)
+                    .filter(|&(x, y)| !data.get(x, y).is_revealed() && !data.get(x, y).is_mine())
+                    .count();
+                data.get(x, y).is_revealed()
+                    && data.get(x, y).neighbouring_mines() > 0
+                    && hidden_safe > 0
+            })
+            .unwrap()
+    }
+
+    #[test]
+    fn chord_waits_for_the_number_to_be_flagged() {
+        let mut data = Data::with_seed(99, 30, 16, 11);
+        data.reveal(15, 8);
+        let (x, y) = chordable(&data);
+        let before = layout(&data);
+        data.chord(x, y);
+        assert_eq!(layout(&data), before);
+
+        for (nx, ny) in data.neighbours(x, y).collect::<Vec<_>>() {
+            if data.get(nx, ny).is_mine() {
+                data.flag(nx, ny);
+            }
+        }
+        assert!(data.can_chord(x, y));
+        data.chord(x, y);
+        assert_ne!(data.status(), GameStatus::Lost);
+        assert!(data
+            .neighbours(x, y)
+            .all(|(x, y)| data.get(x, y).is_revealed() != data.get(x, y).is_mine()));
+        assert!(!data.can_chord(x, y));
+    }
+
+    #[test]
+    fn chord_on_a_wrong_flag_hits_a_mine() {
+        let mut data = Data::with_seed(99, 30, 16, 11);
+        data.reveal(15, 8);
+        let (x, y) = chordable(&data);
+        let count = data.get(x, y).neighbouring_mines() as usize;
+        let hidden = data
+            .neighbours(x, y)
+            .filter(|&(x, y)| !data.get(x, y).is_revealed())
+            .collect::<Vec<_>>();
+        // flag the safe tiles first so at least one mine is left uncovered
+        let mut wrong = hidden.clone();
+        wrong.sort_by_key(|&(x, y)| data.get(x, y).is_mine());
+        for &(nx, ny) in wrong.iter().take(count) {
+            data.flag(nx, ny);
+        }
+        data.chord(x, y);
+        assert_eq!(data.status(), GameStatus::Lost);
+        let death = data.death_pos().unwrap();
+        assert!(hidden.contains(&death) && data.get(death.0, death.1).is_mine());
+    }
+
+    #[test]
+    fn question_marks_cycle_without_counting_as_mines() {
+        let mut data = Data::from_config(GameConfig::BEGINNER);
+        data.flag(0, 0);
+        assert_eq!(data.get(0, 0).state(), TileState::Flagged);
+        assert_eq!(data.mines(), 9);
+        data.flag(0, 0);
+        assert_eq!(data.get(0, 0).state(), TileState::Hidden);
+        assert_eq!(data.mines(), 10);
+
+        data.set_question_marks(true);
+        let cycle = [
+            (TileState::Flagged, 9),
+            (TileState::Question, 10),
+            (TileState::Hidden, 10),
+        ];
+        for (state, mines) in cycle {
+            data.flag(0, 0);
+            assert_eq!(data.get(0, 0).state(), state);
+            assert_eq!(data.mines(), mines);
+        }
+
+        // a question mark doesn't protect the tile like a flag does
+        data.flag(4, 4);
+        data.flag(4, 4);
+        data.reveal(4, 4);
+        assert!(data.get(4, 4).is_revealed());
+        data.restart();
+        assert!(data.question_marks());
+    }
+
+    #[test]
+    fn clicks_place_the_mines_and_stay_on_the_board() {
+        let mut data = Data::with_seed(10, 9, 9, 8);
+        data.click(9, 0);
+        data.click(0, usize::MAX);
+        assert_eq!(data.status(), GameStatus::Ready);
+
+        data.click(4, 4);
+        assert_eq!(data.status(), GameStatus::Playing);
+        assert_eq!(data.first_click(), Some((4, 4)));
+        let mines = layout(&data).iter().filter(|tile| tile.is_mine()).count();
+        assert_eq!(mines, 10);
+        assert!(data.get(4, 4).is_revealed());
+    }
+
+    #[test]
+    #[should_panic(expected = "off the 9x9 board")]
+    fn get_checks_the_column() {
+        // would be the first tile of the second row without the check
+        Data::new(10, 9, 9).get(9, 0);
+    }
+
+    #[test]
+    fn no_guess_boards_clear_by_logic_or_say_so() {
+        for seed in 0..5 {
+            let mut data = Data::from_config(GameConfig {
+                seed: Some(seed),
+                no_guess: true,
+                ..GameConfig::EXPERT
+            });
+            data.reveal(15, 8);
+            assert!(!data.no_guess_failed());
+            assert!(Solver::is_solvable(&data, 15, 8));
+        }
+
+        // too dense to always find a way, but the board is never passed off as something it isn't
+        for seed in 0..3 {
+            let mut data = Data::from_config(GameConfig {
+                seed: Some(seed),
+                no_guess: true,
+                ..GameConfig::new(16, 16, 80)
+            });
+            data.reveal(8, 8);
+            assert_ne!(data.status(), GameStatus::Lost);
+            assert_eq!(data.no_guess_failed(), !Solver::is_solvable(&data, 8, 8));
+        }
+    }
+
+    #[test]
+    fn same_seed_same_board() {
+        let config = GameConfig {
+            seed: Some(42),
+            ..GameConfig::EXPERT
+        };
+        let mut first = Data::from_config(config);
+        first.reveal(5, 5);
+        let mut second = Data::from_config(GameConfig::BEGINNER);
+        play_until_lost(&mut second);
+        second.new_game(config);
+        second.reveal(5, 5);
+        assert_eq!(layout(&first), layout(&second));
+    }
+
+    #[test]
+    fn solution_reveals_every_safe_tile() {
+        let mut data = Data::with_seed(10, 9, 9, 6);
+        assert!(data.solution().is_none());
+        data.reveal(4, 4);
+        let solution = data.solution().unwrap();
+        assert_eq!(solution.status(), GameStatus::Won);
+        assert_eq!(solution.mines(), 0);
+        assert!(layout(&solution).iter().all(
+            |tile| tile.is_flagged() == tile.is_mine() && tile.is_revealed() != tile.is_mine()
+        ));
+        assert_eq!(data.status(), GameStatus::Playing);
+
+        play_until_lost(&mut data);
+        let solution = data.solution().unwrap();
+        assert_eq!(solution.status(), GameStatus::Lost);
+        assert_eq!(solution.death_pos(), data.death_pos());
+    }
+
+    #[test]
+    fn tile_count_stops_at_what_the_counters_hold() {
+        assert_eq!(GameConfig::new(256, 256, 0).tile_count(), Some(65536));
+        assert_eq!(
+            GameConfig::new(65535, 65537, 0).tile_count(),
+            Some(u32::MAX)
+        );
+        assert_eq!(GameConfig::new(65536, 65536, 0).tile_count(), None);
+        assert_eq!(GameConfig::new(usize::MAX, 2, 0).tile_count(), None);
+    }
+
+    #[test]
+    fn boards_past_sixteen_bit_counters() {
+        // one more tile than a u16 can count, all of it opened by one click
+        let mut data = Data::new(0, 256, 256);
+        assert_eq!(data.tiles_left(), 65536);
+        data.reveal(0, 0);
+        assert_eq!(data.tiles_left(), 0);
+        assert_eq!(data.status(), GameStatus::Won);
+
+        // more mines than an i16 can count
+        let mut data = Data::with_seed(40_000, 500, 500, 5);
+        assert_eq!(data.starting_mines(), 40_000);
+        assert_eq!(data.mines(), 40_000);
+        assert_eq!(data.tiles_left(), 210_000);
+        data.reveal(250, 250);
+        let revealed = layout(&data)
+            .iter()
+            .filter(|tile| tile.is_revealed())
+            .count();
+        assert_eq!(data.tiles_left() as usize, 210_000 - revealed);
+        let (x, y) = (0..500)
+            .flat_map(|y| (0..500).map(move |x| (x, y)))
+            .find(|&(x, y)| !data.get(x, y).is_revealed())
+            .unwrap();
+        data.flag(x, y);
+        assert_eq!(data.mines(), 39_999);
+
+        let saved = crate::save::SavedGame::new(&data);
+        assert_eq!(
+            crate::save::SavedGame::parse(&saved.to_string()),
+            Some(saved)
+        );
+
+        // mine counts past the board are capped rather than wrapping
+        let data = Data::new(u32::MAX, 9, 9);
+        assert_eq!(data.starting_mines(), 72);
+        assert_eq!(data.tiles_left(), 9);
+    }
+}
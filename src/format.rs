@@ -0,0 +1,22 @@
+//! Pieces of the plain text format every file the game keeps is written in
+//!
+//! A file starts with a `version N` line, blank lines and lines starting with `#` are left out wherever they
+//! are, and yes or no settings are written as `0` or `1`.
+
+/// The version of the file and its lines after it, `None` if it doesn't start with a version
+pub(crate) fn header(text: &str) -> Option<(u32, impl Iterator<Item = &str>)> {
+    let mut lines = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'));
+    let version = lines.next()?.strip_prefix("version ")?.parse().ok()?;
+    Some((version, lines))
+}
+
+pub(crate) fn flag(field: &str) -> Option<bool> {
+    match field {
+        "0" => Some(false),
+        "1" => Some(true),
+        _ => None,
+    }
+}
@@ -0,0 +1,18 @@
+//! The minesweeper engine, free of any windowing or rendering so it can be driven by bots, tests and tools
+
+extern crate rand;
+extern crate rand_chacha;
+
+pub mod args;
+pub mod config;
+mod data;
+mod format;
+pub mod paths;
+pub mod replay;
+pub mod save;
+pub mod solver;
+pub mod stats;
+mod tile;
+
+pub use data::{time_digits, Data, GameConfig, GameStatus};
+pub use tile::{Tile, TileContent, TileState};
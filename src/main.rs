@@ -1,309 +1,469 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 #![feature(inline_const)]
 
-extern crate rand;
+extern crate minesweeper_rs;
 extern crate wgpu;
 extern crate winit;
 
 mod assets;
+mod cli;
+mod custom_game;
+mod pack;
+mod rasterizer;
+mod sprites;
 mod vertex_buffer_builder;
 mod window;
 
+use crate::custom_game::CustomGame;
+use crate::rasterizer::{background, rasterize};
+use crate::sprites::Sprite;
 use crate::vertex_buffer_builder::VertexBufferBuilder;
 use crate::window::run;
-use rand::Rng;
+use minesweeper_rs::config::Config;
+use minesweeper_rs::paths;
+use minesweeper_rs::replay::{Action, Replay};
+use minesweeper_rs::save::SavedGame;
+use minesweeper_rs::solver::Solver;
+use minesweeper_rs::stats::Stats;
+use minesweeper_rs::{time_digits, Data, GameConfig, GameStatus, TileContent, TileState};
+use std::io;
+use std::path::PathBuf;
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
 use window::Theme;
-use std::hint::unreachable_unchecked;
-use std::time::{SystemTime, UNIX_EPOCH};
 use winit::dpi::{PhysicalPosition, PhysicalSize};
-use winit::event::{ElementState, MouseButton, VirtualKeyCode};
+use winit::event::{ElementState, ModifiersState, MouseButton, VirtualKeyCode};
 use winit::window::Window;
 
 fn main() {
-    pollster::block_on(run());
+    let config = Config::load();
+    match cli::parse(std::env::args().skip(1), &config) {
+        Ok(Some(options)) => pollster::block_on(run(options, config)),
+        Ok(None) => println!("{}", cli::usage()),
+        Err(error) => {
+            eprintln!("error: {error}\n{}", cli::usage());
+            std::process::exit(2)
+        }
+    }
+}
+
+/// The smallest and largest boards, in tiles, that the window can be resized between
+pub const MIN_SIZE: (usize, usize) = (8, 3);
+pub const MAX_SIZE: (usize, usize) = (45, 42);
+
+pub fn title(data: &Data, ui: &Ui) -> String {
+    let mut title = format!("Minesweeper <3 - seed {}", data.seed());
+    if data.no_guess_failed() {
+        title.push_str(" (no guess gave up, may need a guess)");
+    } else if data.no_guess() {
+        title.push_str(" (no guess)");
+    }
+    if data.retried() {
+        title.push_str(" (retry)");
+    } else if data.used_undo() {
+        title.push_str(" (practice)");
+    }
+    if ui.playback.is_some() {
+        title.push_str(" (replay)");
+    }
+    if ui.resume.is_some() {
+        title.push_str(" - press Enter to resume your last game");
+    }
+    title
+}
+
+/// Swaps in a fresh board, carrying over no guess mode
+fn new_board(data: &mut Data, ui: &mut Ui, config: GameConfig) {
+    data.new_game(GameConfig {
+        no_guess: data.no_guess(),
+        ..config
+    });
+    ui.recording = None;
+    ui.playback = None;
+    refresh_probabilities(data, ui);
+}
+
+/// Plays the current board again from its first click, recorded as a replay of its own
+fn retry_board(data: &mut Data, ui: &mut Ui) {
+    let Some((x, y)) = data.first_click() else {
+        return;
+    };
+    data.retry();
+    let mut replay = Replay::new(data);
+    replay.push(0, Action::Reveal, x, y);
+    ui.recording = Some(Recording::new(replay));
+    ui.playback = None;
+    refresh_probabilities(data, ui);
+}
+
+/// A replay being watched, each move is made once the clock reaches it
+pub struct Playback {
+    replay: Replay,
+    started: Instant,
+    next: usize,
+}
+
+impl Playback {
+    pub fn new(replay: Replay) -> Playback {
+        Playback {
+            replay,
+            started: Instant::now(),
+            next: 0,
+        }
+    }
 }
 
-pub struct Data {
+/// Everything the window needs on top of the game itself
+#[derive(Default)]
+pub struct Ui {
     mouse_x: f64,
     mouse_y: f64,
-    tiles_left: u16,
-    placed_mines: bool,
-    mines: i16, // this has to be signed
     mouse_held: bool,
-    starting_mines: u16,
-    death_pos: Option<(usize, usize)>,
-    start_time: u64,
-    board: Vec<u8>,
-    width: usize,
-    height: usize,
-    finish_time: Option<u64>,
+    right_held: bool,
+    middle_held: bool,
+    /// Set once left and right are both down, the first to come back up chords and the other does nothing
+    chording: bool,
     resizing: Option<(u32, u32)>,
+    /// A tile the solver proved safe, shown until the next input
+    hint: Option<(usize, usize)>,
+    show_probabilities: bool,
+    /// Chance of a mine per tile for the heatmap, kept up to date while it's shown
+    probabilities: Option<Vec<f32>>,
+    stats: Stats,
+    show_stats: bool,
+    /// The custom game panel, while it's open
+    custom: Option<CustomGame>,
+    recording: Option<Recording>,
+    pub playback: Option<Playback>,
+    /// The game left unfinished last time, offered until it's resumed or a new one is started
+    pub resume: Option<SavedGame>,
+    /// The settings as last saved
+    pub config: Config,
+    pub modifiers: ModifiersState,
 }
 
-impl Data {
-    pub fn new(mines: u16, width: usize, height: usize) -> Data {
-        Data {
-            mouse_x: 0.0,
-            mouse_y: 0.0,
-            width,
-            height,
-            tiles_left: (width * height - mines as usize) as u16,
-            placed_mines: false,
-            mines: mines as i16,
-            mouse_held: false,
-            starting_mines: mines,
-            death_pos: None,
-            finish_time: None,
-            start_time: unsafe {
-                SystemTime::now()
-                    .duration_since(UNIX_EPOCH)
-                    .unwrap_unchecked()
-            }
-            .as_secs(), // time cannot go backwards
-            board: {
-                let mut vec = Vec::<u8>::with_capacity(width * height);
-                unsafe {
-                    vec.as_mut_ptr().write_bytes(0, vec.capacity());
-                    vec.set_len(vec.capacity());
-                }
-                vec
-            },
-            resizing: None,
+/// Writes the settings back if the board, mode or theme changed, a replay's board isn't one the player picked
+fn remember_settings(data: &Data, ui: &mut Ui, theme: Option<Theme>) {
+    let mut config = ui.config.clone();
+    config.theme = theme.or(config.theme);
+    if ui.playback.is_none() {
+        config.width = data.width();
+        config.height = data.height();
+        config.mines = data.starting_mines();
+        config.no_guess = data.no_guess();
+        config.question_marks = data.question_marks();
+    }
+    if config != ui.config {
+        if let Err(error) = config.save() {
+            eprintln!("couldn't save config: {error}");
         }
+        ui.config = config;
     }
+}
 
-    #[inline(always)]
-    pub fn width(&self) -> usize {
-        self.width
+/// Ctrl, or cmd on macOS, for shortcuts like undo
+fn command_held(ui: &Ui) -> bool {
+    ui.modifiers.ctrl() || ui.modifiers.logo()
+}
+
+/// Whether a panel is on top of the board, which keeps clicks from reaching it
+fn board_covered(ui: &Ui) -> bool {
+    ui.show_stats || ui.custom.is_some()
+}
+
+/// The current game's moves, from when its first move was made
+struct Recording {
+    started: Instant,
+    replay: Replay,
+    /// Where the replay went when the game finished, a game carried on with undo is saved over it
+    path: Option<PathBuf>,
+}
+
+impl Recording {
+    fn new(replay: Replay) -> Recording {
+        Recording {
+            started: Instant::now(),
+            replay,
+            path: None,
+        }
     }
 
-    #[inline(always)]
-    pub fn height(&self) -> usize {
-        self.height
+    fn save(&mut self) -> io::Result<()> {
+        match &self.path {
+            Some(path) => self.replay.save_to(path),
+            None => {
+                self.path = Some(self.replay.save()?);
+                Ok(())
+            }
+        }
     }
+}
 
-    #[inline(always)]
-    pub fn get(&self, x: usize, y: usize) -> u8 {
-        unsafe { *self.board.get_unchecked(y * self.width + x) }
+/// Whether the settings a replay starts with can still change, only until the game's first move is recorded
+fn settings_open(data: &Data, ui: &Ui) -> bool {
+    ui.playback.is_none()
+        && data.status() == GameStatus::Ready
+        && ui
+            .recording
+            .as_ref()
+            .is_none_or(|recording| recording.replay.moves.is_empty())
+}
+
+fn toggle_question_marks(data: &mut Data, ui: &Ui) {
+    if settings_open(data, ui) {
+        data.set_question_marks(!data.question_marks());
     }
+}
 
-    #[inline(always)]
-    pub fn get_mut(&mut self, x: usize, y: usize) -> &mut u8 {
-        unsafe { self.board.get_unchecked_mut(y * self.width + x) }
+fn toggle_no_guess(data: &mut Data, ui: &Ui) {
+    if settings_open(data, ui) {
+        data.set_no_guess(!data.no_guess());
     }
+}
 
-    pub fn get_uv(&self, x: usize, y: usize) -> (u32, u32) {
-        let mouse_x = (self.mouse_x - 12.0) / 16.0;
-        let mouse_y = (self.mouse_y - 55.0) / 16.0;
-        let tile = self.get(x, y);
-        if let Some((death_x, death_y)) = self.death_pos {
-            if (tile >> 2) & 3 != 1 && tile & 1 == 1 {
-                return (0, 0);
-            }
-            if (tile >> 2) & 3 == 1 {
-                return if x != death_x || y != death_y {
-                    (16, 0)
-                } else {
-                    (0, 16)
-                };
-            }
-        }
-        if mouse_x >= 0.0
-            && mouse_y >= 0.0
-            && mouse_x as usize == x
-            && mouse_y as usize == y
-            && self.death_pos.is_none()
-            && self.tiles_left != 0
-        {
-            if tile & 1 == 1 {
-                return (32, 0);
-            }
+/// Makes a move on the board, recording it for the replay
+fn play(data: &mut Data, ui: &mut Ui, action: Action, x: usize, y: usize) {
+    let possible = match action {
+        Action::Undo => data.can_undo(),
+        Action::Redo => data.can_redo(),
+        Action::Chord => data.can_chord(x, y),
+        Action::Reveal | Action::Flag => data.in_bounds(x, y) && !data.status().is_finished(),
+    };
+    if !possible {
+        return;
+    }
+    // only games played from the start can be replayed, not resumed ones
+    if ui.recording.is_none() && data.status() == GameStatus::Ready {
+        ui.recording = Some(Recording::new(Replay::new(data)));
+    }
+    if let Some(recording) = &mut ui.recording {
+        recording
+            .replay
+            .push(recording.started.elapsed().as_millis() as u64, action, x, y);
+    }
+    ui.resume = None;
+    let status = data.status();
+    action.apply(data, x, y);
+    if !status.is_finished() && data.status().is_finished() {
+        game_over(data, ui);
+    }
+    refresh_probabilities(data, ui);
+}
 
-            if (tile >> 1) & 1 == 0 {
-                if self.mouse_held {
-                    return (16, 16);
-                }
-                return (48, 0);
-            }
-        }
-        if tile & 1 == 1 {
-            return (32, 16);
+/// Saves the stats and replay of a game that just finished
+fn game_over(data: &Data, ui: &mut Ui) {
+    ui.stats.record(data);
+    if let Err(error) = ui.stats.save() {
+        eprintln!("couldn't save stats: {error}");
+    }
+    if let Some(recording) = &mut ui.recording {
+        if let Err(error) = recording.save() {
+            eprintln!("couldn't save replay: {error}");
         }
+    }
+}
 
-        if (tile >> 1) & 1 == 0 {
-            return (48, 16);
-        }
+/// Saves the window as it looks now to the `screenshots` folder of the config dir, or the board with every
+/// tile uncovered instead of the current view
+fn screenshot(
+    data: &Data,
+    ui: &Ui,
+    theme: Theme,
+    atlas: &[u8],
+    size: PhysicalSize<u32>,
+    solution: bool,
+) -> io::Result<PathBuf> {
+    let mut builder =
+        VertexBufferBuilder::new(&size, sprites::BUILT_IN.size.0, sprites::BUILT_IN.size.1);
+    if solution {
+        let solved = data
+            .solution()
+            .ok_or_else(|| io::Error::other("there's no solution before the first click"))?;
+        render(&mut builder, &solved, &Ui::default(), Clock::now(&solved));
+    } else {
+        render(&mut builder, data, ui, Clock::now(data));
+    }
+    let image = rasterize(&builder, atlas, sprites::BUILT_IN.size, background(theme));
 
-        return match (tile >> 2) & 3 {
-            0 => (16, 16),
-            1 => (0, 16),
-            _ => match (tile >> 4) & 7 {
-                // check bitmask for unreachable_unchecked assurance
-                0 => (48, 48),
-                1 => (32, 48),
-                2 => (16, 48),
-                3 => (0, 48),
-                4 => (32, 48),
-                5 => (32, 32),
-                6 => (32, 16),
-                7 => (32, 0),
-                _ => unsafe { unreachable_unchecked() }, // literally impossible with bitmask
-            },
-        };
+    let dir = paths::dir("screenshots")?;
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |time| time.as_secs());
+    let suffix = if solution { "-solution" } else { "" };
+    let path = dir.join(format!(
+        "{now}-{}x{}-{}{suffix}.png",
+        data.width(),
+        data.height(),
+        data.seed()
+    ));
+    image.save_png(&path)?;
+    Ok(path)
+}
+
+/// Keeps an unfinished game for next time, or forgets the saved one once it's been finished
+pub fn on_close(data: &Data, ui: &Ui) {
+    if ui.playback.is_some() || ui.resume.is_some() {
+        return;
     }
+    let result = if data.status() == GameStatus::Playing {
+        SavedGame::new(data).save()
+    } else {
+        SavedGame::remove()
+    };
+    if let Err(error) = result {
+        eprintln!("couldn't save game: {error}");
+    }
+}
 
-    pub fn clear_board(&mut self) {
-        unsafe {
-            self.board
-                .as_mut_ptr()
-                .write_bytes(0, self.board.capacity())
-        }
+/// Makes every move of the replay that's due, called once per frame
+pub fn advance_playback(data: &mut Data, ui: &mut Ui) {
+    let Some(playback) = &mut ui.playback else {
+        return;
+    };
+    let elapsed = playback.started.elapsed().as_millis() as u64;
+    let moves = &playback.replay.moves;
+    let start = playback.next;
+    while let Some(step) = moves.get(playback.next).filter(|step| step.time <= elapsed) {
+        step.apply(data);
+        playback.next += 1;
+    }
+    if playback.next != start {
+        refresh_probabilities(data, ui);
     }
+}
 
-    pub fn place_mines(&mut self, avoid_x: usize, avoid_y: usize) {
-        let mut rand = rand::thread_rng();
-        let mut i = 0;
-        while i < self.mines {
-            let x = rand.gen_range(0..self.width);
-            let y = rand.gen_range(0..self.height);
-            if x.wrapping_sub(avoid_x).wrapping_add(1) <= 2
-                && y.wrapping_sub(avoid_y).wrapping_add(1) <= 2
-            {
-                continue;
-            }
+/// Recomputes the heatmap, only after a move or a new board since solving a big board takes a while
+fn refresh_probabilities(data: &Data, ui: &mut Ui) {
+    ui.probabilities = if ui.show_probabilities && data.status() == GameStatus::Playing {
+        let mut solver = Solver::new(data);
+        solver.solve();
+        solver.probabilities()
+    } else {
+        None
+    };
+}
 
-            if (self.get(x, y) >> 2) & 3 == 1 {
-                continue;
-            }
+/// The tile under the mouse while a chord on it is held down, its neighbours are drawn pressed
+fn chord_preview(data: &Data, ui: &Ui) -> Option<(usize, usize)> {
+    if data.status().is_finished() || board_covered(ui) || ui.playback.is_some() {
+        return None;
+    }
+    let (x, y) = hovered_tile(data, ui)?;
+    let click_to_chord = ui.config.click_to_chord && ui.mouse_held && data.get(x, y).is_revealed();
+    (ui.chording || ui.middle_held || click_to_chord).then_some((x, y))
+}
 
-            *self.get_mut(x, y) = 0b00000100;
-
-            let width = self.width;
-            let height = self.height;
-            for &(x, y) in [
-                (x.wrapping_sub(1), y.wrapping_sub(1)),
-                (x, y.wrapping_sub(1)),
-                (x + 1, y.wrapping_sub(1)),
-                (x.wrapping_sub(1), y),
-                (x + 1, y),
-                (x.wrapping_sub(1), y + 1),
-                (x, y + 1),
-                (x + 1, y + 1),
-            ]
-            .iter()
-            .filter(|(x, y)| *x < width && *y < height)
-            {
-                match (self.get(x, y) >> 2) & 3 {
-                    0 => *self.get_mut(x, y) = 0b00001000,
-                    1 => {} // already a mine
-                    2 => *self.get_mut(x, y) = ((((self.get(x, y) >> 4) & 7) + 1) << 4) | 0b1000,
-                    _ => panic!(
-                        "Impossible value {} at {} {}",
-                        (self.get(x, y) >> 2) & 3,
-                        x,
-                        y
-                    ),
-                }
-            }
+fn hovered_tile(data: &Data, ui: &Ui) -> Option<(usize, usize)> {
+    let x = (ui.mouse_x - 12.0) / 16.0;
+    let y = (ui.mouse_y - 55.0) / 16.0;
+    (x >= 0.0 && y >= 0.0 && data.in_bounds(x as usize, y as usize))
+        .then_some((x as usize, y as usize))
+}
+
+/// The built-in sprite called `name`
+#[inline]
+fn sprite(name: &str) -> Sprite {
+    sprites::BUILT_IN.get(name)
+}
 
-            i += 1
+/// Which sprite the tile at `x`, `y` is drawn with
+pub fn tile_sprite(data: &Data, ui: &Ui, x: usize, y: usize) -> &'static str {
+    let mouse_x = (ui.mouse_x - 12.0) / 16.0;
+    let mouse_y = (ui.mouse_y - 55.0) / 16.0;
+    let tile = data.get(x, y);
+    if let Some(death_pos) = data.death_pos() {
+        if tile.is_mine() {
+            return if (x, y) != death_pos {
+                "mine"
+            } else {
+                "mine_exploded"
+            };
+        }
+        if tile.is_flagged() {
+            return "wrong_flag";
         }
     }
-
-    pub fn click(&mut self, x: usize, y: usize) {
-        if self.get(x, y) & 3 != 0 {
-            return;
+    if let Some((chord_x, chord_y)) = chord_preview(data, ui) {
+        if x.abs_diff(chord_x) <= 1
+            && y.abs_diff(chord_y) <= 1
+            && matches!(tile.state(), TileState::Hidden | TileState::Question)
+        {
+            return "pressed";
         }
-
-        match (self.get(x, y) >> 2) & 3 {
-            0 => {
-                let width = self.width;
-                let height = self.height;
-                if self.get(x, y) & 0b10 == 0 {
-                    *self.get_mut(x, y) |= 0b10;
-                    self.tiles_left -= 1;
-                    for &(x, y) in [
-                        (x.wrapping_sub(1), y.wrapping_sub(1)),
-                        (x, y.wrapping_sub(1)),
-                        (x + 1, y.wrapping_sub(1)),
-                        (x.wrapping_sub(1), y),
-                        (x + 1, y),
-                        (x.wrapping_sub(1), y + 1),
-                        (x, y + 1),
-                        (x + 1, y + 1),
-                    ]
-                    .iter()
-                    .filter(|(x, y)| x < &width && y < &height)
-                    {
-                        if self.get(x, y) & 0b10 == 0 {
-                            self.click(x, y)
-                        }
-                    }
-                }
-            }
-            1 => {
-                self.death_pos = Some((x, y));
-                self.finish_time = Some(
-                    unsafe {
-                        std::time::SystemTime::now()
-                            .duration_since(SystemTime::UNIX_EPOCH)
-                            .unwrap_unchecked()
-                    }
-                    .as_secs()
-                        - self.start_time,
-                );
-            }
-            2 => {
-                self.tiles_left -= 1;
-                *self.get_mut(x, y) |= 0b10;
-            }
-            _ => panic!(
-                "Impossible value for tile {:#08b} at {} {}",
-                self.get(x, y),
-                y,
-                x
-            ),
+    }
+    if mouse_x >= 0.0
+        && mouse_y >= 0.0
+        && mouse_x as usize == x
+        && mouse_y as usize == y
+        && !data.status().is_finished()
+    {
+        match tile.state() {
+            TileState::Flagged => return "flag_hover",
+            TileState::Hidden | TileState::Question if ui.mouse_held => return "pressed",
+            TileState::Hidden => return "hidden_hover",
+            TileState::Question => return "question_hover",
+            TileState::Revealed => {}
         }
+    }
 
-        if self.tiles_left == 0 {
-            self.finish_time = Some(
-                unsafe {
-                    std::time::SystemTime::now()
-                        .duration_since(SystemTime::UNIX_EPOCH)
-                        .unwrap_unchecked()
-                }
-                .as_secs()
-                    - self.start_time,
-            );
-        }
+    match tile.state() {
+        TileState::Flagged => "flag",
+        TileState::Hidden => "hidden",
+        TileState::Question => "question",
+        TileState::Revealed => match tile.content() {
+            TileContent::Mine => "mine_exploded",
+            TileContent::Number(count) => match count {
+                0 => "pressed",
+                1 => "number_1",
+                2 => "number_2",
+                3 => "number_3",
+                4 => "number_4",
+                5 => "number_5",
+                6 => "number_6",
+                7 => "number_7",
+                8 => "number_8",
+                _ => unreachable!("a tile can't have {count} neighbouring mines"),
+            },
+        },
     }
+}
 
-    pub fn flag(&mut self, x: usize, y: usize) {
-        if self.get(x, y) & 1 != 0 {
-            self.mines += 1;
-        } else {
-            self.mines -= 1;
-        }
+/// The game's clock as of the frame being drawn, read once by the caller so [`render`] draws the same game the
+/// same way every time
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Clock {
+    /// Milliseconds on the timer, which stops once the game is over
+    pub time: u64,
+    /// Milliseconds since the game was finished, the sheen sweeps across the board from then
+    pub since_finish: Option<u64>,
+}
 
-        *self.get_mut(x, y) ^= 0b1;
+impl Clock {
+    pub fn now(data: &Data) -> Clock {
+        Clock {
+            time: data.elapsed(),
+            since_finish: data.finish_time().map(|finish| {
+                (data.start_time().elapsed().as_millis() as u64).saturating_sub(finish)
+            }),
+        }
     }
 }
 
 #[inline]
-pub fn render(builder: &mut VertexBufferBuilder, data: &Data) {
-    let width: u32 = data.width as u32;
-    let height: u32 = data.height as u32;
+pub fn render(builder: &mut VertexBufferBuilder, data: &Data, ui: &Ui, clock: Clock) {
+    let width: u32 = data.width() as u32;
+    let height: u32 = data.height() as u32;
     let reset_x: u32 = (width * 16 - 2) / 2;
     {
+        let (top, bottom) = (sprite("border_top"), sprite("border_bottom"));
         let mut remaining = builder.window_width() - 20;
         let mut offset = 12;
         while remaining > 0 {
-            builder.draw_texture((offset, 0), (81, 25), (16.min(remaining), 55)); // top
+            builder.draw_texture((offset, 0), top.uv, (top.size.0.min(remaining), top.size.1));
             builder.draw_texture(
                 (offset, builder.window_height() - 8),
-                (81, 96),
-                (16.min(remaining), 8),
-            ); // bottom
+                bottom.uv,
+                (bottom.size.0.min(remaining), bottom.size.1),
+            );
             if remaining >= 8 {
                 remaining -= 8;
                 offset += 8;
@@ -314,15 +474,20 @@ pub fn render(builder: &mut VertexBufferBuilder, data: &Data) {
         }
     }
     {
+        let (left, right) = (sprite("border_left"), sprite("border_right"));
         let mut remaining = builder.window_height() - 63;
         let mut offset = 55;
         while remaining > 0 {
-            builder.draw_texture((0, offset), (69, 80), (12, 16.min(remaining))); // left
+            builder.draw_texture(
+                (0, offset),
+                left.uv,
+                (left.size.0, left.size.1.min(remaining)),
+            );
             builder.draw_texture(
                 (builder.window_width() - 8, offset),
-                (97, 80),
-                (8, 16.min(remaining)),
-            ); // right
+                right.uv,
+                (right.size.0, right.size.1.min(remaining)),
+            );
             if remaining >= 8 {
                 remaining -= 8;
                 offset += 8;
@@ -333,101 +498,124 @@ pub fn render(builder: &mut VertexBufferBuilder, data: &Data) {
         }
     }
 
-    builder.draw_texture((0, 0), (69, 25), (12, 55)); // top left
-    builder.draw_texture((builder.window_width() - 8, 0), (97, 25), (8, 55)); // top right
-    builder.draw_texture((0, builder.window_height() - 8), (69, 96), (12, 8)); // bottom left
-    builder.draw_texture(
+    builder.draw_sprite((0, 0), sprite("corner_top_left"));
+    builder.draw_sprite((builder.window_width() - 8, 0), sprite("corner_top_right"));
+    builder.draw_sprite(
+        (0, builder.window_height() - 8),
+        sprite("corner_bottom_left"),
+    );
+    builder.draw_sprite(
         (builder.window_width() - 8, builder.window_height() - 8),
-        (97, 96),
-        (8, 8),
-    ); // bottom right
+        sprite("corner_bottom_right"),
+    );
 
-    builder.draw_texture((16, 16), (64, 0), (41, 25)); // mines (left) border
-    builder.draw_texture((builder.window_width() - 55, 16), (64, 0), (41, 25)); // timer (right) border
+    builder.draw_sprite((16, 16), sprite("counter")); // mines (left) border
+    builder.draw_sprite((builder.window_width() - 55, 16), sprite("counter")); // timer (right) border
 
     for y in 0..height {
         for x in 0..width {
-            builder.draw_texture(
+            builder.draw_sprite(
                 (12 + x * 16, 55 + y * 16),
-                data.get_uv(x as usize, y as usize),
-                (16, 16),
-            ); // tile
+                sprite(tile_sprite(data, ui, x as usize, y as usize)),
+            );
+        }
+    }
+
+    if let Some(probabilities) = &ui.probabilities {
+        for y in 0..height {
+            for x in 0..width {
+                if !data.get(x as usize, y as usize).is_revealed() {
+                    let probability = probabilities[(y * width + x) as usize];
+                    let red = (probability * 2.0).min(1.0);
+                    let green = ((1.0 - probability) * 2.0).min(1.0);
+                    builder.draw_sprite_tinted(
+                        (12 + x * 16, 55 + y * 16),
+                        sprite("white"),
+                        [(red * 255.0) as u8, (green * 255.0) as u8, 0, 0x90],
+                    ); // mine probability
+                }
+            }
         }
     }
 
-    if data.mouse_held
-        && data.mouse_x as u32 >= reset_x
-        && reset_x + 26 > data.mouse_x as u32
-        && data.mouse_y as u32 >= 15
-        && 41 > data.mouse_y as u32
+    if let Some((x, y)) = ui.hint {
+        if data.in_bounds(x, y) && !data.get(x, y).is_revealed() {
+            builder.draw_sprite((12 + x as u32 * 16, 55 + y as u32 * 16), sprite("hint"));
+        }
+    }
+
+    if ui.show_stats {
+        render_stats(builder, data, ui);
+    }
+    if let Some(custom) = &ui.custom {
+        render_custom(builder, data, custom);
+    }
+
+    if ui.mouse_held
+        && ui.mouse_x as u32 >= reset_x
+        && reset_x + 26 > ui.mouse_x as u32
+        && ui.mouse_y as u32 >= 15
+        && 41 > ui.mouse_y as u32
     {
-        builder.draw_texture((reset_x, 15), (105, 78), (26, 26)); // pressed
-    } else if data.death_pos.is_some() {
-        builder.draw_texture((reset_x, 15), (105, 52), (26, 26)); // dead
-    } else if data.tiles_left == 0 {
-        builder.draw_texture((reset_x, 15), (105, 0), (26, 26)); // sunglasses
+        builder.draw_sprite((reset_x, 15), sprite("face_pressed"));
+    } else if data.status() == GameStatus::Lost {
+        builder.draw_sprite((reset_x, 15), sprite("face_lost"));
+    } else if data.status() == GameStatus::Won {
+        builder.draw_sprite((reset_x, 15), sprite("face_won")); // sunglasses
     } else {
-        builder.draw_texture((reset_x, 15), (105, 26), (26, 26)); // normal
+        builder.draw_sprite((reset_x, 15), sprite("face"));
     }
 
     // mines left
-    let mines = if let Some((width, _)) = data.resizing {
-        ((width - 20) / 16) as i16
+    let mines = if let Some((width, _)) = ui.resizing {
+        format!("{:>3}", (width - 20) / 16)
     } else {
-        data.mines
+        data.mine_counter()
     };
-    format!("{:>3}", mines)
+    mines
         .bytes()
         .take(3)
-        .map(get_num_uv)
+        .map(digit_sprite)
         .enumerate()
         .rev()
-        .for_each(|(index, uv)| builder.draw_texture((17 + index as u32 * 13, 17), uv, (13, 23)));
+        .for_each(|(index, name)| builder.draw_sprite((17 + index as u32 * 13, 17), sprite(name)));
 
-    // seconds right
-    let seconds = if let Some((_, height)) = data.resizing {
-        ((height - 63) / 16) as u64
+    // seconds right, with as many decimals as fit once the game is over
+    let (time, dot) = if let Some((_, height)) = ui.resizing {
+        (format!("{:>3}", (height - 63) / 16), None)
     } else {
-        if data.placed_mines {
-            if let Some(time) = data.finish_time {
-                time
-            } else {
-                unsafe {
-                    SystemTime::now()
-                        .duration_since(UNIX_EPOCH)
-                        .unwrap_unchecked()
-                }
-                .as_secs()
-                    - data.start_time
-            }
-        } else {
-            0
-        }
+        Data::clock_digits(clock.time, clock.since_finish.is_some())
     };
-    format!("{:>3}", seconds)
-        .bytes()
+    time.bytes()
         .take(3)
-        .map(get_num_uv)
+        .map(digit_sprite)
         .enumerate()
         .rev()
-        .for_each(|(index, uv)| {
-            builder.draw_texture(
+        .for_each(|(index, name)| {
+            builder.draw_sprite(
                 (builder.window_width() - 54 + index as u32 * 13, 17),
-                uv,
-                (13, 23),
+                sprite(name),
             )
         });
+    if let Some(index) = dot {
+        builder.draw_sprite(
+            (
+                builder.window_width() - 54 + index as u32 * 13 + 12,
+                17 + 21,
+            ),
+            sprite("dot"),
+        );
+    }
 
     // sheen time!!
-    if let Some(finish_time) = data.finish_time.map(|x| (x + data.start_time) as u128 * 5) {
-        let elapsed = unsafe { std::time::SystemTime::now().duration_since(SystemTime::UNIX_EPOCH).unwrap_unchecked() }.as_millis() as f64 / 200.0;
-        let offset = elapsed - finish_time as f64;
-        let offset = if offset >= 6.0 { offset - 6.0 } else { 0.0 };  // estimated time since vsync, overshot because ofc
+    if let Some(since_finish) = clock.since_finish {
+        let offset = since_finish as f64 / 200.0;
+        let offset = if offset >= 6.0 { offset - 6.0 } else { 0.0 }; // estimated time since vsync, overshot because ofc
         let offset = (offset * offset * offset) as u32;
-        for x in 0..data.width as u32 {
-            for y in 0..data.height as u32 {
-                if offset >= x + y + 1 {
-                    builder.draw_texture((12 + x * 16, 55 + y * 16), (131, 0), (16, 16));
+        for x in 0..width {
+            for y in 0..height {
+                if offset > x + y {
+                    builder.draw_sprite((12 + x * 16, 55 + y * 16), sprite("sheen"));
                 }
             }
         }
@@ -436,21 +624,157 @@ pub fn render(builder: &mut VertexBufferBuilder, data: &Data) {
     //    builder.draw_texture((0, 0), (0, 0), (256, 256));
 }
 
+/// Blanks out the board for a panel to go on top
+fn render_panel_background(builder: &mut VertexBufferBuilder, data: &Data) {
+    for y in 0..data.height() as u32 {
+        for x in 0..data.width() as u32 {
+            builder.draw_sprite((12 + x * 16, 55 + y * 16), sprite("pressed"));
+        }
+    }
+}
+
+/// Where the `index`th icon and counter of a panel go, filling columns top to bottom, `None` if it doesn't fit on the board
+fn panel_slot(data: &Data, index: usize) -> Option<(u32, u32)> {
+    let width = data.width() as u32;
+    let height = data.height() as u32;
+    let rows = ((height * 16 - 4) / 28).max(1);
+    let x = 16 + index as u32 / rows * 101;
+    let y = 59 + index as u32 % rows * 28;
+    (x + 97 <= 12 + width * 16 && y + 26 <= 55 + height * 16).then_some((x, y))
+}
+
+/// Covers the board with the stats for its size and mine count
+fn render_stats(builder: &mut VertexBufferBuilder, data: &Data, ui: &Ui) {
+    render_panel_background(builder, data);
+
+    let record = ui
+        .stats
+        .get((data.width(), data.height(), data.starting_mines()));
+    let best_time = match record.best_time {
+        Some(time) => time_digits(time, 5),
+        None => ("    -".to_owned(), None),
+    };
+    let entries = [
+        (
+            "icon_wins",
+            (format!("{:>5}", record.wins.min(99999)), None),
+        ),
+        (
+            "icon_losses",
+            (format!("{:>5}", record.losses.min(99999)), None),
+        ),
+        (
+            "icon_streak",
+            (format!("{:>5}", record.streak.min(99999)), None),
+        ),
+        (
+            "icon_best_streak",
+            (format!("{:>5}", record.best_streak.min(99999)), None),
+        ),
+        ("icon_best_time", best_time),
+    ];
+    // whatever doesn't fit on tiny boards is left out
+    for (index, (icon, (value, dot))) in entries.iter().enumerate() {
+        if let Some((x, y)) = panel_slot(data, index) {
+            builder.draw_sprite((x, y), sprite(icon));
+            draw_counter(builder, (x + 30, y), value, *dot);
+        }
+    }
+}
+
+/// Fields of the custom game panel that fit on the board, as many pages as it takes to keep the selected one shown
+fn custom_fields<'a>(
+    data: &'a Data,
+    custom: &CustomGame,
+) -> impl Iterator<Item = (usize, (u32, u32))> + 'a {
+    let fitting = (0..4)
+        .take_while(|&index| panel_slot(data, index).is_some())
+        .count();
+    let first = custom.selected / fitting * fitting;
+    (first..(first + fitting).min(4))
+        .filter_map(move |field| Some((field, panel_slot(data, field - first)?)))
+}
+
+/// The custom game panel, with the selected field's icon lit and anything invalid faded
+fn render_custom(builder: &mut VertexBufferBuilder, data: &Data, custom: &CustomGame) {
+    render_panel_background(builder, data);
+
+    const ICONS: [&str; 4] = ["icon_width", "icon_height", "icon_mines", "icon_density"];
+    let invalid = custom.invalid();
+    for (field, (x, y)) in custom_fields(data, custom) {
+        let icon_tint = if field == custom.selected {
+            [0xff; 4]
+        } else {
+            [0xa0, 0xa0, 0xa0, 0xff]
+        };
+        builder.draw_sprite_tinted((x, y), sprite(ICONS[field]), icon_tint);
+        let digit_tint = if invalid[field] {
+            [0xff, 0xff, 0xff, 0x60]
+        } else {
+            [0xff; 4]
+        };
+        draw_counter_tinted(
+            builder,
+            (x + 30, y),
+            &format!("{:>4}", custom.fields[field]),
+            None,
+            digit_tint,
+        );
+    }
+}
+
+/// Digits in a frame as wide as the text, like the mine counter and timer, with an optional decimal point after the digit at `dot`
+#[inline]
+fn draw_counter(
+    builder: &mut VertexBufferBuilder,
+    pos: (u32, u32),
+    text: &str,
+    dot: Option<usize>,
+) {
+    draw_counter_tinted(builder, pos, text, dot, [0xff; 4]);
+}
+
+/// Tints the digits, leaving the frame as is
+fn draw_counter_tinted(
+    builder: &mut VertexBufferBuilder,
+    pos: (u32, u32),
+    text: &str,
+    dot: Option<usize>,
+    tint: [u8; 4],
+) {
+    let (x, y) = pos;
+    builder.draw_sprite((x, y), sprite("counter_left"));
+    builder.draw_sprite((x + 1 + text.len() as u32 * 13, y), sprite("counter_right"));
+    for (index, name) in text.bytes().map(digit_sprite).enumerate() {
+        let x = x + 1 + index as u32 * 13;
+        builder.draw_sprite((x, y), sprite("counter_top"));
+        builder.draw_sprite((x, y + 24), sprite("counter_bottom"));
+        builder.draw_sprite_tinted((x, y + 1), sprite(name), tint);
+    }
+    if let Some(index) = dot {
+        builder.draw_sprite_tinted(
+            (x + 1 + index as u32 * 13 + 12, y + 22),
+            sprite("dot"),
+            tint,
+        );
+    }
+}
+
 #[inline]
-fn get_num_uv(char: u8) -> (u32, u32) {
+fn digit_sprite(char: u8) -> &'static str {
     match char {
-        b'-' => (0, 110),
-        b'0' => (52, 87),
-        b'1' => (39, 87),
-        b'2' => (26, 87),
-        b'3' => (13, 87),
-        b'4' => (0, 87),
-        b'5' => (52, 64),
-        b'6' => (39, 64),
-        b'7' => (26, 64),
-        b'8' => (13, 64),
-        b'9' => (0, 64),
-        _ => (65, 1),
+        b'-' => "digit_minus",
+        b'0' => "digit_0",
+        b'1' => "digit_1",
+        b'2' => "digit_2",
+        b'3' => "digit_3",
+        b'4' => "digit_4",
+        b'5' => "digit_5",
+        b'6' => "digit_6",
+        b'7' => "digit_7",
+        b'8' => "digit_8",
+        b'9' => "digit_9",
+        _ => "digit_blank",
     }
 }
 
@@ -459,122 +783,109 @@ pub fn mouse_click(
     state: &ElementState,
     button: &MouseButton,
     data: &mut Data,
+    ui: &mut Ui,
     window: &mut Window,
     window_state: &mut crate::window::State,
 ) {
-    fit_to_size(data, window, window_state);
-    let reset_x: u32 = (data.width as u32 * 16 - 2) / 2;
+    fit_to_size(data, ui, window, window_state);
+    if *state == ElementState::Pressed {
+        ui.hint = None;
+    }
+    let reset_x: u32 = (data.width() as u32 * 16 - 2) / 2;
+    let x = (ui.mouse_x - 12.0) as i32;
+    let y = (ui.mouse_y - 55.0) as i32;
+    let pressed = *state == ElementState::Pressed;
+    let on_board = x >= 0 && y >= 0 && !board_covered(ui) && ui.playback.is_none();
     match button {
         MouseButton::Left => {
-            data.mouse_held = *state == ElementState::Pressed;
-            if *state == ElementState::Released {
-                let x: i32 = (data.mouse_x - 12.0) as i32;
-                let y: i32 = (data.mouse_y - 55.0) as i32;
-                if x >= 0 && y >= 0 && data.tiles_left != 0 && data.death_pos.is_none() {
-                    let x = (x / 16) as usize;
-                    let y = (y / 16) as usize;
-                    if x < data.width && y < data.height {
-                        if !data.placed_mines {
-                            data.placed_mines = true;
-                            data.start_time = unsafe {
-                                SystemTime::now()
-                                    .duration_since(UNIX_EPOCH)
-                                    .unwrap_unchecked()
-                            }
-                            .as_secs();
-                            data.place_mines(x, y);
-                        }
-                        data.click(x, y);
+            ui.mouse_held = pressed;
+            if pressed && ui.right_held {
+                ui.chording = true;
+            } else if !pressed && ui.chording {
+                chord_release(data, ui, ui.right_held, x, y);
+            } else if !pressed {
+                let (tile_x, tile_y) = (x as usize / 16, y as usize / 16);
+                if ui.custom.is_some() {
+                    select_custom_field(data, ui);
+                } else if x >= 0 && y >= 0 && !data.status().is_finished() && !board_covered(ui) {
+                    if ui.playback.is_none() {
+                        let action = if ui.config.click_to_chord
+                            && data.in_bounds(tile_x, tile_y)
+                            && data.get(tile_x, tile_y).is_revealed()
+                        {
+                            Action::Chord
+                        } else {
+                            Action::Reveal
+                        };
+                        play(data, ui, action, tile_x, tile_y);
                     }
-                } else if data.mouse_x as u32 >= reset_x
-                    && reset_x + 26 > data.mouse_x as u32
-                    && data.mouse_y as u32 >= 15
+                } else if ui.mouse_x as u32 >= reset_x
+                    && reset_x + 26 > ui.mouse_x as u32
+                    && ui.mouse_y as u32 >= 15
                     && -14 > y
                 {
-                    data.death_pos = None;
-                    data.finish_time = None;
-                    data.placed_mines = false;
-                    data.mines = data.starting_mines as i16;
-                    data.tiles_left = ((data.width * data.height) as i16 - data.mines) as u16;
-                    data.clear_board();
+                    data.restart();
+                    ui.recording = None;
+                    ui.playback = None;
+                    refresh_probabilities(data, ui);
                 }
             }
         }
         MouseButton::Right => {
-            if *state == ElementState::Pressed {
-                let x = (data.mouse_x - 12.0) as i32;
-                let y = (data.mouse_y - 55.0) as i32;
-                if x >= 0 && y >= 0 && data.tiles_left != 0 && data.death_pos.is_none() {
-                    let x = x as usize / 16;
-                    let y = y as usize / 16;
-                    if x < data.width && y < data.height && (data.get(x, y) >> 1) & 1 == 0 {
-                        data.flag(x, y);
-                    }
-                }
+            ui.right_held = pressed;
+            if pressed && ui.mouse_held {
+                ui.chording = true;
+            } else if !pressed && ui.chording {
+                chord_release(data, ui, ui.mouse_held, x, y);
+            } else if pressed && on_board {
+                play(data, ui, Action::Flag, x as usize / 16, y as usize / 16);
             }
         }
         MouseButton::Middle => {
-            if *state == ElementState::Pressed {
-                let x = (data.mouse_x - 12.0) as i32;
-                let y = (data.mouse_y - 55.0) as i32;
-                if x >= 0 && y >= 0 && data.tiles_left != 0 && data.death_pos.is_none() {
-                    let x = x as usize / 16;
-                    let y = y as usize / 16;
-                    if x < data.width && y < data.height {
-                        if !data.placed_mines {
-                            data.placed_mines = true;
-                            data.start_time = unsafe {
-                                SystemTime::now()
-                                    .duration_since(UNIX_EPOCH)
-                                    .unwrap_unchecked()
-                            }
-                            .as_secs(); // time cannot go backwards
-                            data.place_mines(x, y);
-                        }
-                        data.click(x, y);
-                        let width = data.width;
-                        let height = data.height;
-                        for &(x, y) in [
-                            (x.wrapping_sub(1), y.wrapping_sub(1)),
-                            (x, y.wrapping_sub(1)),
-                            (x + 1, y.wrapping_sub(1)),
-                            (x.wrapping_sub(1), y),
-                            (x + 1, y),
-                            (x.wrapping_sub(1), y + 1),
-                            (x, y + 1),
-                            (x + 1, y + 1),
-                        ]
-                        .iter()
-                        .filter(|(x, y)| *x < width && *y < height)
-                        {
-                            data.click(x, y);
-                        }
-                    }
-                }
+            ui.middle_held = pressed;
+            if !pressed && on_board {
+                play(data, ui, Action::Chord, x as usize / 16, y as usize / 16);
             }
         }
         MouseButton::Other(_) => {}
     }
 }
 
+/// Chords when the first of left and right comes back up, and stops chording once the other one does
+fn chord_release(data: &mut Data, ui: &mut Ui, other_held: bool, x: i32, y: i32) {
+    if !other_held {
+        ui.chording = false;
+    } else if x >= 0 && y >= 0 && !board_covered(ui) && ui.playback.is_none() {
+        play(data, ui, Action::Chord, x as usize / 16, y as usize / 16);
+    }
+}
+
 #[inline]
 pub fn mouse_moved(
     position: &PhysicalPosition<f64>,
-    data: &mut Data,
+    data: &Data,
+    ui: &mut Ui,
     window: &mut Window,
     state: &mut crate::window::State,
 ) {
-    data.mouse_x = position.x;
-    data.mouse_y = position.y;
-    fit_to_size(data, window, state);
+    ui.mouse_x = position.x;
+    ui.mouse_y = position.y;
+    fit_to_size(data, ui, window, state);
 }
 
-pub fn fit_to_size(data: &mut Data, window: &mut Window, state: &mut crate::window::State) {
-    if let Some((width, height)) = data.resizing.take() {
+/// Snaps the window to whole tiles once the player is done resizing, and only then saves the new size
+pub fn fit_to_size(
+    data: &Data,
+    ui: &mut Ui,
+    window: &mut Window,
+    state: &mut crate::window::State,
+) {
+    if let Some((width, height)) = ui.resizing.take() {
         let width = ((width - 20) & !0b1111) + 20;
         let height = ((height - 63) & !0b1111) + 63;
         window.set_inner_size(PhysicalSize::new(width, height));
         state.resize(PhysicalSize::new(width, height));
+        remember_settings(data, ui, None);
     }
 }
 
@@ -582,124 +893,234 @@ pub fn fit_to_size(data: &mut Data, window: &mut Window, state: &mut crate::wind
 pub fn key_input(
     input: winit::event::KeyboardInput,
     data: &mut Data,
+    ui: &mut Ui,
     window: &mut Window,
     state: &mut crate::window::State,
 ) {
-    fit_to_size(data, window, state);
+    fit_to_size(data, ui, window, state);
+    let mut theme = None;
     if input.state == ElementState::Released {
+        ui.hint = None;
         if let Some(x) = input.virtual_keycode {
-            if x == VirtualKeyCode::B {
-                data.width = 9;
-                data.height = 9;
-                data.placed_mines = false;
-                data.starting_mines = 10;
-                data.mouse_x = 0.0;
-                data.mouse_y = 0.0;
-                data.mines = 10;
-                data.tiles_left = (9 * 9) - 10;
-                data.death_pos = None;
-                data.finish_time = None;
-                data.board = {
-                    let mut vec = Vec::<u8>::with_capacity(9 * 9);
-                    unsafe {
-                        vec.as_mut_ptr().write_bytes(0, 9 * 9);
-                        vec.set_len(9 * 9);
-                    }
-                    vec
-                };
-                let size = PhysicalSize::new((20 + 16 * 9) as u32, (63 + 16 * 9) as u32);
-                window.set_inner_size(size.clone());
-                state.resize(size);
+            if x == VirtualKeyCode::F12 {
+                match screenshot(
+                    data,
+                    ui,
+                    state.theme,
+                    state.atlases.get(state.theme),
+                    window.inner_size(),
+                    ui.modifiers.shift(),
+                ) {
+                    Ok(path) => println!("saved screenshot to {}", path.display()),
+                    Err(error) => eprintln!("couldn't save screenshot: {error}"),
+                }
+            } else if ui.custom.is_some() {
+                custom_game_key(x, data, ui, window, state);
+            } else if x == VirtualKeyCode::B {
+                start_board(data, ui, window, state, GameConfig::BEGINNER);
             } else if x == VirtualKeyCode::I {
-                data.width = 16;
-                data.height = 16;
-                data.placed_mines = false;
-                data.starting_mines = 40;
-                data.mouse_x = 0.0;
-                data.mouse_y = 0.0;
-                data.mines = 40;
-                data.tiles_left = (16 * 16) - 40;
-                data.death_pos = None;
-                data.finish_time = None;
-                data.board = {
-                    let mut vec = Vec::<u8>::with_capacity(16 * 16);
-                    unsafe {
-                        vec.as_mut_ptr().write_bytes(0, 16 * 16);
-                        vec.set_len(16 * 16);
-                    }
-                    vec
-                };
-                let size = PhysicalSize::new((20 + 16 * 16) as u32, (63 + 16 * 16) as u32);
-                window.set_inner_size(size.clone());
-                state.resize(size);
+                start_board(data, ui, window, state, GameConfig::INTERMEDIATE);
             } else if x == VirtualKeyCode::E {
-                data.width = 30;
-                data.height = 16;
-                data.placed_mines = false;
-                data.starting_mines = 99;
-                data.mouse_x = 0.0;
-                data.mouse_y = 0.0;
-                data.mines = 99;
-                data.tiles_left = (30 * 16) - 99;
-                data.death_pos = None;
-                data.finish_time = None;
-                data.board = {
-                    let mut vec = Vec::<u8>::with_capacity(30 * 16);
-                    unsafe {
-                        vec.as_mut_ptr().write_bytes(0, 30 * 16);
-                        vec.set_len(30 * 16);
-                    }
-                    vec
-                };
-                let size = PhysicalSize::new((20 + 16 * 30) as u32, (63 + 16 * 16) as u32);
-                window.set_inner_size(size.clone());
-                state.resize(size);
+                start_board(data, ui, window, state, GameConfig::EXPERT);
+            } else if x == VirtualKeyCode::Z && command_held(ui) && ui.playback.is_none() {
+                play(data, ui, Action::Undo, 0, 0);
+            } else if x == VirtualKeyCode::Y && command_held(ui) && ui.playback.is_none() {
+                play(data, ui, Action::Redo, 0, 0);
+            } else if x == VirtualKeyCode::R {
+                retry_board(data, ui);
+            } else if x == VirtualKeyCode::C {
+                ui.custom = Some(CustomGame::new(data));
+                ui.show_stats = false;
+            } else if x == VirtualKeyCode::Return {
+                if let Some(saved) = ui.resume.take() {
+                    let question_marks = data.question_marks();
+                    *data = saved.into_data();
+                    data.set_question_marks(question_marks);
+                    ui.recording = None;
+                    refresh_probabilities(data, ui);
+                    fit_window_to_board(data, ui, window, state);
+                }
+            } else if x == VirtualKeyCode::H && data.status() == GameStatus::Playing {
+                let mut solver = Solver::new(data);
+                solver.solve();
+                ui.hint = solver.safe().next();
+            } else if x == VirtualKeyCode::S {
+                ui.show_stats = !ui.show_stats;
+            } else if x == VirtualKeyCode::P {
+                ui.show_probabilities = !ui.show_probabilities;
+                refresh_probabilities(data, ui);
+            } else if x == VirtualKeyCode::Q {
+                toggle_question_marks(data, ui);
+            } else if x == VirtualKeyCode::N {
+                toggle_no_guess(data, ui);
             } else if x == VirtualKeyCode::Up
-                && data.starting_mines as usize + 9 < data.width * data.height
+                && data.starting_mines() as usize + 9 < data.width() * data.height()
             {
-                data.placed_mines = false;
-                data.mouse_x = 0.0;
-                data.mouse_y = 0.0;
-                data.starting_mines += 1;
-                data.mines = data.starting_mines as i16;
-                data.tiles_left = (data.width * data.height) as u16 - data.starting_mines;
-                data.death_pos = None;
-                data.finish_time = None;
-                data.clear_board(); // keep size
-            } else if x == VirtualKeyCode::Down && data.starting_mines > 0 {
-                data.placed_mines = false;
-                data.mouse_x = 0.0;
-                data.mouse_y = 0.0;
-                data.starting_mines -= 1;
-                data.mines = data.starting_mines as i16;
-                data.tiles_left = (data.width * data.height) as u16 - data.starting_mines;
-                data.death_pos = None;
-                data.finish_time = None;
-                data.clear_board(); // keep size
+                ui.mouse_x = 0.0;
+                ui.mouse_y = 0.0;
+                let config = GameConfig {
+                    mines: data.starting_mines() + 1,
+                    seed: None,
+                    ..data.config()
+                };
+                new_board(data, ui, config); // keep size
+            } else if x == VirtualKeyCode::Down && data.starting_mines() > 0 {
+                ui.mouse_x = 0.0;
+                ui.mouse_y = 0.0;
+                let config = GameConfig {
+                    mines: data.starting_mines() - 1,
+                    seed: None,
+                    ..data.config()
+                };
+                new_board(data, ui, config); // keep size
             } else if x == VirtualKeyCode::L {
                 state.theme = Theme::Light;
+                theme = Some(Theme::Light);
             } else if x == VirtualKeyCode::D {
                 state.theme = Theme::Dark;
+                theme = Some(Theme::Dark);
+            }
+        }
+    }
+    remember_settings(data, ui, theme);
+}
+
+/// Selects the field of the custom game panel under the mouse, if there is one
+fn select_custom_field(data: &Data, ui: &mut Ui) {
+    let (mouse_x, mouse_y) = (ui.mouse_x as u32, ui.mouse_y as u32);
+    let Some(custom) = &mut ui.custom else {
+        return;
+    };
+    let hovered = custom_fields(data, custom)
+        .find(|&(_, (x, y))| (x..x + 84).contains(&mouse_x) && (y..y + 26).contains(&mouse_y));
+    if let Some((field, _)) = hovered {
+        custom.selected = field;
+    }
+}
+
+/// Typing into the custom game panel, which takes every key while it's open
+fn custom_game_key(
+    key: VirtualKeyCode,
+    data: &mut Data,
+    ui: &mut Ui,
+    window: &mut Window,
+    state: &mut crate::window::State,
+) {
+    let Some(custom) = &mut ui.custom else {
+        return;
+    };
+    match key {
+        VirtualKeyCode::Key0 | VirtualKeyCode::Numpad0 => custom.type_digit(0),
+        VirtualKeyCode::Key1 | VirtualKeyCode::Numpad1 => custom.type_digit(1),
+        VirtualKeyCode::Key2 | VirtualKeyCode::Numpad2 => custom.type_digit(2),
+        VirtualKeyCode::Key3 | VirtualKeyCode::Numpad3 => custom.type_digit(3),
+        VirtualKeyCode::Key4 | VirtualKeyCode::Numpad4 => custom.type_digit(4),
+        VirtualKeyCode::Key5 | VirtualKeyCode::Numpad5 => custom.type_digit(5),
+        VirtualKeyCode::Key6 | VirtualKeyCode::Numpad6 => custom.type_digit(6),
+        VirtualKeyCode::Key7 | VirtualKeyCode::Numpad7 => custom.type_digit(7),
+        VirtualKeyCode::Key8 | VirtualKeyCode::Numpad8 => custom.type_digit(8),
+        VirtualKeyCode::Key9 | VirtualKeyCode::Numpad9 => custom.type_digit(9),
+        VirtualKeyCode::Back => custom.backspace(),
+        VirtualKeyCode::Tab | VirtualKeyCode::Down => custom.select_next(),
+        VirtualKeyCode::Up => custom.select_previous(),
+        VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => {
+            if let Some((width, height, mines)) = custom.board() {
+                ui.custom = None;
+                start_board(
+                    data,
+                    ui,
+                    window,
+                    state,
+                    GameConfig::new(width, height, mines),
+                );
             }
         }
+        VirtualKeyCode::Escape | VirtualKeyCode::C => ui.custom = None,
+        _ => {}
     }
 }
 
-pub fn on_resize(size: PhysicalSize<u32>, data: &mut Data) {
-    data.resizing = Some((size.width, size.height));
-    data.width = (size.width as usize - 20) / 16;
-    data.height = (size.height as usize - 63) / 16;
-    data.placed_mines = false;
-    data.mines = data.starting_mines as i16;
-    data.tiles_left = (data.width * data.height) as u16 - data.starting_mines;
-    data.death_pos = None;
-    data.finish_time = None;
-    data.board = {
-        let mut vec = Vec::<u8>::with_capacity(data.width * data.height);
-        unsafe {
-            vec.as_mut_ptr().write_bytes(0, vec.capacity());
-            vec.set_len(vec.capacity());
+/// Every board picked from the keyboard starts here, the window follows its size
+fn start_board(
+    data: &mut Data,
+    ui: &mut Ui,
+    window: &mut Window,
+    state: &mut crate::window::State,
+    config: GameConfig,
+) {
+    new_board(data, ui, config);
+    fit_window_to_board(data, ui, window, state);
+}
+
+fn fit_window_to_board(
+    data: &Data,
+    ui: &mut Ui,
+    window: &mut Window,
+    state: &mut crate::window::State,
+) {
+    ui.mouse_x = 0.0;
+    ui.mouse_y = 0.0;
+    let size = PhysicalSize::new(
+        (20 + 16 * data.width()) as u32,
+        (63 + 16 * data.height()) as u32,
+    );
+    window.set_inner_size(size);
+    state.resize(size);
+}
+
+pub fn on_resize(size: PhysicalSize<u32>, data: &mut Data, ui: &mut Ui) {
+    ui.resizing = Some((size.width, size.height));
+    ui.hint = None;
+    let width = (size.width as usize - 20) / 16;
+    let height = (size.height as usize - 63) / 16;
+    // resize events also arrive when the window opens, which mustn't throw away a seeded or replayed board
+    if (width, height) != (data.width(), data.height()) {
+        let config = GameConfig {
+            width,
+            height,
+            seed: None,
+            ..data.config()
+        };
+        new_board(data, ui, config);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn settings_stay_as_the_replay_recorded_them() {
+        let mut data = Data::with_seed(40, 16, 16, 6);
+        let mut ui = Ui::default();
+        toggle_no_guess(&mut data, &ui);
+        toggle_question_marks(&mut data, &ui);
+        assert!(data.no_guess() && data.question_marks());
+
+        // a flag starts the recording before the first reveal places the mines
+        play(&mut data, &mut ui, Action::Flag, 0, 0);
+        assert_eq!(data.status(), GameStatus::Ready);
+        toggle_no_guess(&mut data, &ui);
+        toggle_question_marks(&mut data, &ui);
+        assert!(data.no_guess() && data.question_marks());
+
+        play(&mut data, &mut ui, Action::Flag, 0, 0);
+        play(&mut data, &mut ui, Action::Reveal, 8, 8);
+        assert_eq!(data.status(), GameStatus::Playing);
+        toggle_question_marks(&mut data, &ui);
+        assert!(data.question_marks());
+
+        let replay = &ui.recording.as_ref().unwrap().replay;
+        let mut played = replay.data();
+        for step in &replay.moves {
+            step.apply(&mut played);
+        }
+        for (x, y) in (0..16 * 16).map(|index| (index % 16, index / 16)) {
+            assert_eq!(
+                played.get(x, y),
+                data.get(x, y),
+                "({x}, {y}) played back differently"
+            );
         }
-        vec
     }
 }
@@ -0,0 +1,277 @@
+//! Theme packs, sprites drawn over the built-in atlases
+//!
+//! A pack is a folder in the `themes` folder of the config dir, picked with `pack = "name"` in the config or
+//! `--pack <name>`. It holds a `light.png` and a `dark.png`, or just one of them for both themes, and a
+//! `sprites.txt` saying where the sprites it replaces are in those images:
+//!
+//! ```text
+//! # name = x y width height
+//! face = 0 0 26 26
+//! digit_0 = 26 0 13 23
+//! ```
+//!
+//! Names are the ones in [`sprites::BUILT_IN`], and sprites keep their built-in size since the layout around
+//! them doesn't move. Anything the pack doesn't list looks like it always does.
+
+use crate::assets;
+use crate::sprites::{self, Sprite};
+use crate::window::Theme;
+use minesweeper_rs::paths::config_dir;
+use std::fs::{self, File};
+use std::path::Path;
+
+/// The atlas for each theme, as raw RGBA laid out like [`sprites::BUILT_IN`]
+pub struct Atlases {
+    dark: Vec<u8>,
+    light: Vec<u8>,
+}
+
+impl Atlases {
+    pub fn built_in() -> Atlases {
+        Atlases {
+            dark: assets::DARK_ATLAS.to_vec(),
+            light: assets::LIGHT_ATLAS.to_vec(),
+        }
+    }
+
+    /// The pack's atlases, or the built-in ones with a warning if it can't be used
+    pub fn with_pack(pack: Option<&str>) -> Atlases {
+        let Some(name) = pack else {
+            return Atlases::built_in();
+        };
+        Atlases::load(name).unwrap_or_else(|error| {
+            eprintln!("couldn't load theme pack {name}, using the built-in one: {error}");
+            Atlases::built_in()
+        })
+    }
+
+    pub fn load(name: &str) -> Result<Atlases, String> {
+        // a name is just a folder, not a way to read any file
+        if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+            return Err(format!("{name:?} isn't a pack name"));
+        }
+        let dir = config_dir()
+            .ok_or("no config directory")?
+            .join("themes")
+            .join(name);
+        let manifest = fs::read_to_string(dir.join("sprites.txt")).map_err(|error| {
+            format!(
+                "couldn't read {}: {error}",
+                dir.join("sprites.txt").display()
+            )
+        })?;
+        let sprites = parse_manifest(&manifest)?;
+
+        let image = |file: &str| {
+            let path = dir.join(file);
+            path.exists().then(|| {
+                read_png(&path)
+                    .map_err(|error| format!("couldn't read {}: {error}", path.display()))
+            })
+        };
+        let (light, dark) = match (
+            image("light.png").transpose()?,
+            image("dark.png").transpose()?,
+        ) {
+            (Some(light), Some(dark)) => (light, dark),
+            (Some(image), None) | (None, Some(image)) => (image.clone(), image),
+            (None, None) => {
+                return Err(format!(
+                    "{} has neither light.png nor dark.png",
+                    dir.display()
+                ))
+            }
+        };
+        Ok(Atlases {
+            dark: apply(assets::DARK_ATLAS, &dark, &sprites)?,
+            light: apply(assets::LIGHT_ATLAS, &light, &sprites)?,
+        })
+    }
+
+    pub fn get(&self, theme: Theme) -> &[u8] {
+        match theme {
+            Theme::Dark => &self.dark,
+            Theme::Light => &self.light,
+        }
+    }
+}
+
+/// A sprite the pack draws its own way
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Replacement {
+    sprite: Sprite,
+    /// Top left corner in the pack's image
+    from: (u32, u32),
+}
+
+fn parse_manifest(text: &str) -> Result<Vec<Replacement>, String> {
+    let mut sprites = Vec::new();
+    for (number, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let error = |message: String| format!("sprites.txt line {}: {message}", number + 1);
+        let (name, rect) = line
+            .split_once('=')
+            .ok_or_else(|| error("expected name = x y width height".to_owned()))?;
+        let name = name.trim();
+        let sprite = sprites::BUILT_IN
+            .find(name)
+            .ok_or_else(|| error(format!("there's no sprite called {name}")))?;
+        let rect = rect
+            .split_whitespace()
+            .map(|value| value.parse::<u32>())
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(|_| error("expected name = x y width height".to_owned()))?;
+        let [x, y, width, height] = rect[..] else {
+            return Err(error("expected name = x y width height".to_owned()));
+        };
+        if (width, height) != sprite.size {
+            return Err(error(format!(
+                "{name} has to be {}x{}, not {width}x{height}",
+                sprite.size.0, sprite.size.1
+            )));
+        }
+        sprites.push(Replacement {
+            sprite,
+            from: (x, y),
+        });
+    }
+    Ok(sprites)
+}
+
+/// A copy of `atlas` with every sprite replaced by its rectangle from `image`
+fn apply(atlas: &[u8], image: &Image, sprites: &[Replacement]) -> Result<Vec<u8>, String> {
+    let mut atlas = atlas.to_vec();
+    let atlas_width = sprites::BUILT_IN.size.0 as usize;
+    for &Replacement {
+        sprite,
+        from: (x, y),
+    } in sprites
+    {
+        let (width, height) = sprite.size;
+        if x.checked_add(width).is_none_or(|right| right > image.width)
+            || y.checked_add(height)
+                .is_none_or(|bottom| bottom > image.height)
+        {
+            return Err(format!(
+                "{} at {x},{y} goes past the edge of the {}x{} image",
+                sprite.name, image.width, image.height
+            ));
+        }
+        for row in 0..height as usize {
+            let from = ((y as usize + row) * image.width as usize + x as usize) * 4;
+            let to = ((sprite.uv.1 as usize + row) * atlas_width + sprite.uv.0 as usize) * 4;
+            atlas[to..to + width as usize * 4]
+                .copy_from_slice(&image.pixels[from..from + width as usize * 4]);
+        }
+    }
+    Ok(atlas)
+}
+
+#[derive(Clone)]
+struct Image {
+    width: u32,
+    height: u32,
+    /// RGBA whatever the file stored
+    pixels: Vec<u8>,
+}
+
+fn read_png(path: &Path) -> Result<Image, png::DecodingError> {
+    let mut decoder = png::Decoder::new(File::open(path)?);
+    decoder.set_transformations(png::Transformations::EXPAND | png::Transformations::STRIP_16);
+    let mut reader = decoder.read_info()?;
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    buffer.truncate(info.buffer_size());
+    let pixels = match info.color_type {
+        png::ColorType::Rgba => buffer,
+        png::ColorType::Rgb => buffer
+            .chunks(3)
+            .flat_map(|rgb| [rgb[0], rgb[1], rgb[2], 0xFF])
+            .collect(),
+        png::ColorType::GrayscaleAlpha => buffer
+            .chunks(2)
+            .flat_map(|ga| [ga[0], ga[0], ga[0], ga[1]])
+            .collect(),
+        png::ColorType::Grayscale => buffer.iter().flat_map(|&g| [g, g, g, 0xFF]).collect(),
+        // expanded to RGB by the decoder
+        png::ColorType::Indexed => unreachable!(),
+    };
+    Ok(Image {
+        width: info.width,
+        height: info.height,
+        pixels,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn manifest_names_and_sizes_are_checked() {
+        let sprites =
+            parse_manifest("# a comment\n\nface = 0 0 26 26 # the usual one\ndigit_0 = 26 0 13 23")
+                .unwrap();
+        assert_eq!(
+            sprites[0],
+            Replacement {
+                sprite: sprites::BUILT_IN.get("face"),
+                from: (0, 0)
+            }
+        );
+        assert_eq!(
+            sprites[1],
+            Replacement {
+                sprite: sprites::BUILT_IN.get("digit_0"),
+                from: (26, 0)
+            }
+        );
+
+        assert!(parse_manifest("smiley = 0 0 26 26").is_err());
+        assert!(parse_manifest("face = 0 0 16 16").is_err());
+        assert!(parse_manifest("face = 0 0 26").is_err());
+        assert!(parse_manifest("face 0 0 26 26").is_err());
+    }
+
+    #[test]
+    fn sprites_replace_their_spot_in_the_atlas() {
+        let red = Image {
+            width: 30,
+            height: 30,
+            pixels: [0xFF, 0, 0, 0xFF].repeat(900),
+        };
+        let face = sprites::BUILT_IN.get("face");
+        let atlas = apply(
+            assets::LIGHT_ATLAS,
+            &red,
+            &[Replacement {
+                sprite: face,
+                from: (4, 4),
+            }],
+        )
+        .unwrap();
+        let pixel =
+            |x: u32, y: u32| &atlas[((y * sprites::BUILT_IN.size.0 + x) * 4) as usize..][..4];
+        assert_eq!(pixel(face.uv.0, face.uv.1), [0xFF, 0, 0, 0xFF]);
+        assert_eq!(pixel(face.uv.0 + 25, face.uv.1 + 25), [0xFF, 0, 0, 0xFF]);
+        // only the face changed
+        let outside = (face.uv.1 + 26) * sprites::BUILT_IN.size.0 + face.uv.0;
+        assert_eq!(
+            atlas[outside as usize * 4..],
+            assets::LIGHT_ATLAS[outside as usize * 4..]
+        );
+
+        assert!(apply(
+            assets::LIGHT_ATLAS,
+            &red,
+            &[Replacement {
+                sprite: face,
+                from: (5, 5)
+            }]
+        )
+        .is_err());
+    }
+}
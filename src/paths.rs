@@ -0,0 +1,37 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+/// The per-user directory settings, statistics and saves go in, `None` if the environment doesn't say where that is
+pub fn config_dir() -> Option<PathBuf> {
+    let base = if cfg!(windows) {
+        env::var_os("APPDATA").map(PathBuf::from)
+    } else if cfg!(target_os = "macos") {
+        env::var_os("HOME").map(|home| PathBuf::from(home).join("Library/Application Support"))
+    } else {
+        env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))
+    };
+    base.map(|base| base.join("minesweeper-rs"))
+}
+
+/// A folder in the config dir, created if it isn't there yet
+pub fn dir(name: &str) -> io::Result<PathBuf> {
+    let dir = config_dir().ok_or_else(no_config_dir)?.join(name);
+    fs::create_dir_all(&dir)?;
+    Ok(dir)
+}
+
+/// Replaces the file `name` in the config dir, creating the dir first if need be
+pub fn write(name: &str, contents: impl AsRef<[u8]>) -> io::Result<()> {
+    let dir = config_dir().ok_or_else(no_config_dir)?;
+    fs::create_dir_all(&dir)?;
+    fs::write(dir.join(name), contents)
+}
+
+fn no_config_dir() -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, "no config directory")
+}
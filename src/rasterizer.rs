@@ -0,0 +1,324 @@
+//! Draws what a [`VertexBufferBuilder`] holds on the CPU, for tests and screenshots that can't count on a GPU
+//!
+//! Follows the pipeline `State` sets up: triangles are drawn in order with back faces culled and alpha
+//! blending, the atlas is sampled nearest neighbour and repeats, and z is ignored as there's no depth buffer.
+//! Blending happens on linear colours like it does on an sRGB surface.
+
+use crate::vertex_buffer_builder::VertexBufferBuilder;
+use crate::window::Theme;
+use std::fs::File;
+use std::io;
+use std::io::BufWriter;
+use std::path::Path;
+
+/// Position, texture coordinates and tint, as `draw_texture_z_tinted` writes them
+const VERTEX_SIZE: usize = 24;
+/// Vertex positions are snapped to 1/256th of a pixel, so edges shared by two triangles meet exactly
+const SUBPIXELS: f64 = 256.0;
+
+/// An RGBA image, row by row from the top left
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn save_png(&self, path: &Path) -> io::Result<()> {
+        let mut encoder =
+            png::Encoder::new(BufWriter::new(File::create(path)?), self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+        encoder.set_compression(png::Compression::Best);
+        encoder
+            .write_header()
+            .and_then(|mut writer| writer.write_image_data(&self.pixels))
+            .map_err(io::Error::other)
+    }
+}
+
+/// The linear grey the window is cleared to before anything is drawn
+pub fn background(theme: Theme) -> f64 {
+    match theme {
+        Theme::Dark => 0.0461488424,
+        Theme::Light => 0.535641609,
+    }
+}
+
+#[derive(Copy, Clone)]
+struct Vertex {
+    /// In subpixels from the top left of the window
+    x: i64,
+    y: i64,
+    u: f64,
+    v: f64,
+    tint: [f64; 4],
+}
+
+/// Draws the builder's triangles over the background, sampling `atlas` as raw RGBA texels of `atlas_size`
+pub fn rasterize(
+    builder: &VertexBufferBuilder,
+    atlas: &[u8],
+    atlas_size: (u32, u32),
+    background: f64,
+) -> Image {
+    let (width, height) = (builder.window_width(), builder.window_height());
+    let clear = [
+        encode(background),
+        encode(background),
+        encode(background),
+        0xFF,
+    ];
+    let mut image = Image {
+        width,
+        height,
+        pixels: clear.repeat((width * height) as usize),
+    };
+    let vertex = |index: u16| {
+        let bytes = &builder.vertices()[index as usize * VERTEX_SIZE..][..VERTEX_SIZE];
+        let float = |offset: usize| {
+            f32::from_ne_bytes(bytes[offset..offset + 4].try_into().unwrap()) as f64
+        };
+        Vertex {
+            x: ((float(0) + 1.0) / 2.0 * width as f64 * SUBPIXELS).round() as i64,
+            y: ((1.0 - float(4)) / 2.0 * height as f64 * SUBPIXELS).round() as i64,
+            u: float(12) * atlas_size.0 as f64,
+            v: float(16) * atlas_size.1 as f64,
+            tint: [0, 1, 2, 3].map(|channel| bytes[20 + channel] as f64 / 255.0),
+        }
+    };
+    let indices = builder
+        .indices()
+        .chunks_exact(2)
+        .map(|index| u16::from_le_bytes([index[0], index[1]]))
+        .collect::<Vec<_>>();
+    for triangle in indices.chunks_exact(3) {
+        draw_triangle(
+            &mut image,
+            [
+                vertex(triangle[0]),
+                vertex(triangle[1]),
+                vertex(triangle[2]),
+            ],
+            atlas,
+            atlas_size,
+        );
+    }
+    image
+}
+
+fn draw_triangle(image: &mut Image, vertices: [Vertex; 3], atlas: &[u8], atlas_size: (u32, u32)) {
+    let [a, b, c] = vertices;
+    // counter clockwise on screen is clockwise here with y pointing down, anything else is a back face
+    let area = edge(a, b, c.x, c.y);
+    if area >= 0 {
+        return;
+    }
+    let left = a.x.min(b.x).min(c.x).div_euclid(SUBPIXELS as i64).max(0);
+    let top = a.y.min(b.y).min(c.y).div_euclid(SUBPIXELS as i64).max(0);
+    let right = (a.x.max(b.x).max(c.x).div_euclid(SUBPIXELS as i64) + 1).min(image.width as i64);
+    let bottom = (a.y.max(b.y).max(c.y).div_euclid(SUBPIXELS as i64) + 1).min(image.height as i64);
+    for y in top..bottom {
+        for x in left..right {
+            // sampled at the pixel's centre
+            let (px, py) = (
+                x * SUBPIXELS as i64 + SUBPIXELS as i64 / 2,
+                y * SUBPIXELS as i64 + SUBPIXELS as i64 / 2,
+            );
+            let weights = [edge(b, c, px, py), edge(c, a, px, py), edge(a, b, px, py)];
+            let edges = [(b, c), (c, a), (a, b)];
+            if !weights
+                .iter()
+                .zip(edges)
+                .all(|(&weight, (from, to))| covers(weight, from, to))
+            {
+                continue;
+            }
+            let weights = weights.map(|weight| weight as f64 / area as f64);
+            let u = weights[0] * a.u + weights[1] * b.u + weights[2] * c.u;
+            let v = weights[0] * a.v + weights[1] * b.v + weights[2] * c.v;
+            let texel_x = (u.floor() as i64).rem_euclid(atlas_size.0 as i64) as usize;
+            let texel_y = (v.floor() as i64).rem_euclid(atlas_size.1 as i64) as usize;
+            let texel = &atlas[(texel_y * atlas_size.0 as usize + texel_x) * 4..][..4];
+            let tint = a.tint;
+            let alpha = texel[3] as f64 / 255.0 * tint[3];
+            let pixel =
+                &mut image.pixels[(y as usize * image.width as usize + x as usize) * 4..][..4];
+            for channel in 0..3 {
+                let source = decode(texel[channel]) * tint[channel];
+                let destination = decode(pixel[channel]);
+                pixel[channel] = encode(source * alpha + destination * (1.0 - alpha));
+            }
+            pixel[3] = ((alpha + pixel[3] as f64 / 255.0 * (1.0 - alpha)) * 255.0).round() as u8;
+        }
+    }
+}
+
+/// Twice the signed area of the triangle from `from` to `to` to the point, negative on the inside
+fn edge(from: Vertex, to: Vertex, x: i64, y: i64) -> i64 {
+    (to.x - from.x) * (y - from.y) - (to.y - from.y) * (x - from.x)
+}
+
+/// Whether a point on the inside of an edge belongs to the triangle, points right on an edge only belong to
+/// one of the two triangles sharing it so nothing is blended twice
+fn covers(weight: i64, from: Vertex, to: Vertex) -> bool {
+    weight < 0 || weight == 0 && (to.y > from.y || to.y == from.y && to.x < from.x)
+}
+
+fn decode(value: u8) -> f64 {
+    let value = value as f64 / 255.0;
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+fn encode(value: f64) -> u8 {
+    let value = value.clamp(0.0, 1.0);
+    let value = if value <= 0.0031308 {
+        value * 12.92
+    } else {
+        1.055 * value.powf(1.0 / 2.4) - 0.055
+    };
+    (value * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pack::Atlases;
+    use crate::{render, sprites, Clock, Ui};
+    use minesweeper_rs::{Data, GameStatus};
+    use std::path::PathBuf;
+    use winit::dpi::PhysicalSize;
+
+    /// Renders the game like the window would at its natural size and compares it to `src/snapshots/<name>.png`
+    ///
+    /// The clock always reads zero, and a finished game is drawn the moment it ended before the sheen starts.
+    /// Run with `UPDATE_SNAPSHOTS=1` to write the snapshots instead, after checking the change is wanted.
+    fn assert_snapshot(name: &str, data: &Data, ui: &Ui, theme: Theme) {
+        let size = PhysicalSize::new(
+            20 + 16 * data.width() as u32,
+            63 + 16 * data.height() as u32,
+        );
+        let mut builder =
+            VertexBufferBuilder::new(&size, sprites::BUILT_IN.size.0, sprites::BUILT_IN.size.1);
+        let clock = Clock {
+            time: 0,
+            since_finish: data.status().is_finished().then_some(0),
+        };
+        render(&mut builder, data, ui, clock);
+        let image = rasterize(
+            &builder,
+            Atlases::built_in().get(theme),
+            sprites::BUILT_IN.size,
+            background(theme),
+        );
+
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+            .join("src/snapshots")
+            .join(format!("{name}.png"));
+        if std::env::var_os("UPDATE_SNAPSHOTS").is_some() {
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            image.save_png(&path).unwrap();
+            return;
+        }
+        let decoder = png::Decoder::new(
+            File::open(&path).unwrap_or_else(|_| panic!("no snapshot at {}", path.display())),
+        );
+        let mut reader = decoder.read_info().unwrap();
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels).unwrap();
+        assert_eq!(
+            (info.width, info.height),
+            (image.width, image.height),
+            "{name} changed size"
+        );
+        let different = pixels
+            .chunks(4)
+            .zip(image.pixels.chunks(4))
+            .filter(|(a, b)| a != b)
+            .count();
+        assert!(different == 0, "{different} pixels of {name} changed, rerun with UPDATE_SNAPSHOTS=1 if that's intended");
+    }
+
+    fn reveal_all(data: &mut Data) {
+        for y in 0..data.height() {
+            for x in 0..data.width() {
+                if !data.get(x, y).is_mine() {
+                    data.reveal(x, y);
+                }
+            }
+        }
+    }
+
+    fn first_mine(data: &Data) -> (usize, usize) {
+        (0..data.height())
+            .flat_map(|y| (0..data.width()).map(move |x| (x, y)))
+            .find(|&(x, y)| data.get(x, y).is_mine())
+            .unwrap()
+    }
+
+    #[test]
+    fn fresh_board() {
+        let data = Data::with_seed(10, 9, 9, 1);
+        assert_snapshot("fresh_light", &data, &Ui::default(), Theme::Light);
+        assert_snapshot("fresh_dark", &data, &Ui::default(), Theme::Dark);
+    }
+
+    #[test]
+    fn board_in_play() {
+        let mut data = Data::with_seed(40, 16, 16, 2);
+        data.reveal(8, 8);
+        let (x, y) = first_mine(&data);
+        data.flag(x, y);
+        // hovering the top left tile
+        let ui = Ui {
+            mouse_x: 20.0,
+            mouse_y: 60.0,
+            ..Default::default()
+        };
+        assert_snapshot("playing", &data, &ui, Theme::Light);
+    }
+
+    #[test]
+    fn smiley_states() {
+        // clicking the face
+        let data = Data::with_seed(10, 9, 9, 3);
+        let ui = Ui {
+            mouse_x: 90.0,
+            mouse_y: 25.0,
+            mouse_held: true,
+            ..Default::default()
+        };
+        assert_snapshot("smiley_pressed", &data, &ui, Theme::Light);
+
+        let mut data = Data::with_seed(10, 9, 9, 3);
+        data.reveal(4, 4);
+        let (x, y) = first_mine(&data);
+        data.reveal(x, y);
+        assert_eq!(data.status(), GameStatus::Lost);
+        assert_snapshot("smiley_lost", &data, &Ui::default(), Theme::Light);
+
+        let mut data = Data::with_seed(10, 9, 9, 3);
+        reveal_all(&mut data);
+        assert_eq!(data.status(), GameStatus::Won);
+        assert_snapshot("smiley_won", &data, &Ui::default(), Theme::Dark);
+    }
+
+    #[test]
+    fn mine_counter() {
+        // more flags than mines takes the counter below zero
+        let mut data = Data::with_seed(10, 9, 9, 4);
+        for x in 0..9 {
+            data.flag(x, 0);
+            data.flag(x, 8);
+        }
+        assert_eq!(data.mines(), -8);
+        assert_snapshot("counter_negative", &data, &Ui::default(), Theme::Dark);
+
+        let data = Data::with_seed(999, 45, 42, 4);
+        assert_snapshot("counter_full", &data, &Ui::default(), Theme::Light);
+    }
+}
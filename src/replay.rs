@@ -0,0 +1,309 @@
+//! Recordings of a game that can be played back move for move
+//!
+//! Mines are placed from the seed and the first reveal, so the board settings and a timestamped list of
+//! moves are all it takes to rebuild a game. Stored as text in the `replays` folder of
+//! [`config_dir`](paths::config_dir):
+//!
+//! ```text
+//! version 3
+//! # width height mines seed no_guess question_marks
+//! 9 9 10 1234567890 0 1
+//! # millis action x y
+//! 0 reveal 4 4
+//! 1830 flag 2 3
+//! 2410 chord 3 4
+//! 3050 undo 0 0
+//! ```
+//!
+//! Undo and redo don't act on a tile, their coordinates are always 0. Version 1 replays predate question
+//! marks and are played back without them. No guess boards were generated differently before version 3, so
+//! older replays of them are rejected rather than played on the wrong board.
+
+use crate::format;
+use crate::paths;
+use crate::{Data, GameConfig};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const VERSION: u32 = 3;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Action {
+    Reveal,
+    Flag,
+    Chord,
+    Undo,
+    Redo,
+}
+
+impl Action {
+    fn name(self) -> &'static str {
+        match self {
+            Action::Reveal => "reveal",
+            Action::Flag => "flag",
+            Action::Chord => "chord",
+            Action::Undo => "undo",
+            Action::Redo => "redo",
+        }
+    }
+
+    pub fn apply(self, data: &mut Data, x: usize, y: usize) {
+        match self {
+            Action::Reveal => data.reveal(x, y),
+            Action::Flag => data.flag(x, y),
+            Action::Chord => data.chord(x, y),
+            Action::Undo => {
+                data.undo();
+            }
+            Action::Redo => {
+                data.redo();
+            }
+        }
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Move {
+    /// Milliseconds since the first move of the game
+    pub time: u64,
+    pub action: Action,
+    pub x: usize,
+    pub y: usize,
+}
+
+impl Move {
+    #[inline]
+    pub fn apply(self, data: &mut Data) {
+        self.action.apply(data, self.x, self.y);
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Replay {
+    pub width: usize,
+    pub height: usize,
+    pub mines: u32,
+    pub seed: u64,
+    pub no_guess: bool,
+    /// Changes what flagging a flagged tile does, so it has to match for the moves to play out the same
+    pub question_marks: bool,
+    pub moves: Vec<Move>,
+}
+
+impl Replay {
+    /// An empty recording of a game that hasn't started yet
+    pub fn new(data: &Data) -> Replay {
+        Replay {
+            width: data.width(),
+            height: data.height(),
+            mines: data.starting_mines(),
+            seed: data.seed(),
+            no_guess: data.no_guess(),
+            question_marks: data.question_marks(),
+            moves: Vec::new(),
+        }
+    }
+
+    /// The board as it was before the first move
+    pub fn data(&self) -> Data {
+        let mut data = Data::with_seed(self.mines, self.width, self.height, self.seed);
+        data.set_no_guess(self.no_guess);
+        data.set_question_marks(self.question_marks);
+        data
+    }
+
+    pub fn push(&mut self, time: u64, action: Action, x: usize, y: usize) {
+        self.moves.push(Move { time, action, x, y });
+    }
+
+    pub fn load(path: &Path) -> io::Result<Replay> {
+        Self::parse(&fs::read_to_string(path)?)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "not a valid replay"))
+    }
+
+    /// Unlike stats a replay is all or nothing, a single bad move would send the rest of the game off course
+    pub fn parse(text: &str) -> Option<Replay> {
+        let (version, mut lines) = format::header(text)?;
+        let mut fields = lines.next()?.split_whitespace().collect::<Vec<_>>();
+        match version {
+            1 => fields.push("0"),
+            2 | VERSION => {}
+            _ => return None,
+        }
+        let [width, height, mines, seed, no_guess, question_marks] = fields[..] else {
+            return None;
+        };
+        let mut replay = Replay {
+            width: width.parse().ok()?,
+            height: height.parse().ok()?,
+            mines: mines.parse().ok()?,
+            seed: seed.parse().ok()?,
+            no_guess: format::flag(no_guess)?,
+            question_marks: format::flag(question_marks)?,
+            moves: Vec::new(),
+        };
+        if version < 3 && replay.no_guess {
+            return None;
+        }
+        let tiles = GameConfig::new(replay.width, replay.height, replay.mines).tile_count()?;
+        if replay.width == 0 || replay.height == 0 || replay.mines.checked_add(9)? > tiles {
+            return None;
+        }
+        for line in lines {
+            let fields = line.split_whitespace().collect::<Vec<_>>();
+            let [time, action, x, y] = fields[..] else {
+                return None;
+            };
+            let action = match action {
+                "reveal" => Action::Reveal,
+                "flag" => Action::Flag,
+                "chord" => Action::Chord,
+                "undo" => Action::Undo,
+                "redo" => Action::Redo,
+                _ => return None,
+            };
+            replay.push(time.parse().ok()?, action, x.parse().ok()?, y.parse().ok()?);
+        }
+        Some(replay)
+    }
+
+    /// Writes the replay to a new file named after the board and when it was saved, returning where it went
+    pub fn save(&self) -> io::Result<PathBuf> {
+        let dir = paths::dir("replays")?;
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |time| time.as_secs());
+        let path = dir.join(format!(
+            "{now}-{}x{}-{}-{}.txt",
+            self.width, self.height, self.mines, self.seed
+        ));
+        self.save_to(&path)?;
+        Ok(path)
+    }
+
+    /// Overwrites an earlier save, for a game that carried on after it finished
+    pub fn save_to(&self, path: &Path) -> io::Result<()> {
+        fs::write(path, self.to_string())
+    }
+}
+
+impl std::fmt::Display for Replay {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "version {VERSION}")?;
+        writeln!(f, "# width height mines seed no_guess question_marks")?;
+        writeln!(
+            f,
+            "{} {} {} {} {} {}",
+            self.width,
+            self.height,
+            self.mines,
+            self.seed,
+            self.no_guess as u8,
+            self.question_marks as u8
+        )?;
+        writeln!(f, "# millis action x y")?;
+        for step in &self.moves {
+            writeln!(
+                f,
+                "{} {} {} {}",
+                step.time,
+                step.action.name(),
+                step.x,
+                step.y
+            )?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::GameStatus;
+
+    /// A lost game with flags, question marks, a chord and an undo in it, recorded as it was played
+    fn recorded() -> (Replay, Data) {
+        let mut data = Data::with_seed(40, 16, 16, 11);
+        data.set_question_marks(true);
+        let mut replay = Replay::new(&data);
+        let mut play = |data: &mut Data, action: Action, x: usize, y: usize| {
+            replay.push(replay.moves.len() as u64 * 100, action, x, y);
+            action.apply(data, x, y);
+        };
+        play(&mut data, Action::Reveal, 8, 8);
+        let tiles = (0..16 * 16)
+            .map(|index| (index % 16, index / 16))
+            .collect::<Vec<_>>();
+        let mine = *tiles
+            .iter()
+            .find(|&&(x, y)| data.get(x, y).is_mine())
+            .unwrap();
+        play(&mut data, Action::Flag, mine.0, mine.1);
+        play(&mut data, Action::Flag, mine.0, mine.1);
+        play(&mut data, Action::Flag, 0, 15);
+        if let Some(&(x, y)) = tiles.iter().find(|&&(x, y)| data.can_chord(x, y)) {
+            play(&mut data, Action::Chord, x, y);
+        }
+        play(&mut data, Action::Undo, 0, 0);
+        play(&mut data, Action::Redo, 0, 0);
+        play(&mut data, Action::Reveal, mine.0, mine.1);
+        (replay, data)
+    }
+
+    #[test]
+    fn replays_read_back_the_same() {
+        let (replay, _) = recorded();
+        assert_eq!(Replay::parse(&replay.to_string()), Some(replay.clone()));
+
+        let no_guess = Replay {
+            no_guess: true,
+            ..replay
+        };
+        assert_eq!(Replay::parse(&no_guess.to_string()), Some(no_guess));
+    }
+
+    #[test]
+    fn broken_replays_are_rejected() {
+        let header = "9 9 10 1234 0 1";
+        assert!(Replay::parse(&format!("version 3\n{header}\n0 reveal 4 4\n")).is_some());
+        assert!(Replay::parse(&format!("version 4\n{header}\n0 reveal 4 4\n")).is_none());
+        assert!(Replay::parse(&format!("version 0\n{header}\n")).is_none());
+        assert!(Replay::parse(&format!("{header}\n0 reveal 4 4\n")).is_none());
+        assert!(Replay::parse(&format!("version 3\n{header}\n0 dig 4 4\n")).is_none());
+        assert!(Replay::parse(&format!("version 3\n{header}\n0 reveal 4\n")).is_none());
+        assert!(Replay::parse(&format!("version 3\n{header}\n0 reveal 4 -4\n")).is_none());
+        assert!(Replay::parse("version 3\n9 9 73 1234 0 1\n").is_none());
+
+        // no guess boards from before version 3 were laid out differently
+        assert!(Replay::parse("version 2\n9 9 10 1234 0 1\n").is_some());
+        assert!(Replay::parse("version 2\n9 9 10 1234 1 1\n").is_none());
+        assert!(Replay::parse("version 1\n9 9 10 1234 1\n").is_none());
+        let old = Replay::parse("version 1\n9 9 10 1234 0\n0 reveal 4 4\n").unwrap();
+        assert!(!old.question_marks);
+    }
+
+    #[test]
+    fn playback_ends_on_the_same_board() {
+        let (replay, data) = recorded();
+        let mut played = Replay::parse(&replay.to_string()).unwrap().data();
+        for step in &replay.moves {
+            step.apply(&mut played);
+        }
+        assert_eq!(played.status(), GameStatus::Lost);
+        assert_eq!(played.status(), data.status());
+        assert_eq!(played.death_pos(), data.death_pos());
+        assert_eq!(played.tiles_left(), data.tiles_left());
+        assert_eq!(played.mines(), data.mines());
+        for y in 0..data.height() {
+            for x in 0..data.width() {
+                assert_eq!(
+                    played.get(x, y),
+                    data.get(x, y),
+                    "({x}, {y}) played back differently"
+                );
+            }
+        }
+    }
+}
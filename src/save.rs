@@ -0,0 +1,236 @@
+//! A game in progress, kept on disk between runs so closing the window doesn't lose it
+//!
+//! Stored as `save.txt` in [`config_dir`]. After the version line comes one line of settings, then the
+//! board one row per line with every tile as two hex digits of its [`Tile`] byte:
+//!
+//! ```text
+//! version 2
+//! # width height starting_mines mines tiles_left placed_mines elapsed_millis seed no_guess practice
+//! 9 9 10 9 52 1 15230 1234567890 0 0
+//! # tiles: bit 0 flagged, 1 revealed, 2 mine, 3 question mark, 4-7 neighbouring mines
+//! 0202121414...
+//! ```
+//!
+//! Version 1 saves lack `practice` and are read as games that still count towards stats.
+//!
+//! Saves that don't add up, like a neighbour count that doesn't match the mines around it, are rejected
+//! as a whole rather than resuming a broken board. So are saves of games that haven't started or are already
+//! over, since only a game in play is ever saved.
+
+use crate::format;
+use crate::paths::{self, config_dir};
+use crate::{Data, GameConfig, Tile, TileState};
+use std::fmt::Write;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+const VERSION: u32 = 2;
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SavedGame {
+    pub width: usize,
+    pub height: usize,
+    pub starting_mines: u32,
+    pub mines: i64,
+    pub tiles_left: u32,
+    pub placed_mines: bool,
+    /// Milliseconds on the clock when the game was saved
+    pub elapsed: u64,
+    pub seed: u64,
+    pub no_guess: bool,
+    /// Whether the game has stopped counting towards stats, see [`Data::counts_for_records`]
+    pub practice: bool,
+    pub board: Vec<Tile>,
+}
+
+impl SavedGame {
+    pub fn new(data: &Data) -> SavedGame {
+        SavedGame {
+            width: data.width(),
+            height: data.height(),
+            starting_mines: data.starting_mines(),
+            mines: data.mines(),
+            tiles_left: data.tiles_left(),
+            placed_mines: data.placed_mines(),
+            elapsed: data.elapsed(),
+            seed: data.seed(),
+            no_guess: data.no_guess(),
+            practice: !data.counts_for_records(),
+            board: (0..data.height())
+                .flat_map(|y| (0..data.width()).map(move |x| (x, y)))
+                .map(|(x, y)| data.get(x, y))
+                .collect(),
+        }
+    }
+
+    /// The game as it was saved, with the clock carrying on from where it stopped
+    #[inline]
+    pub fn into_data(self) -> Data {
+        Data::from_saved(self)
+    }
+
+    pub fn path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join("save.txt"))
+    }
+
+    /// The saved game, `None` if there isn't one or it can't be used
+    pub fn load() -> Option<SavedGame> {
+        Self::parse(&fs::read_to_string(Self::path()?).ok()?)
+    }
+
+    pub fn parse(text: &str) -> Option<SavedGame> {
+        let (version, mut lines) = format::header(text)?;
+        let mut fields = lines.next()?.split_whitespace().collect::<Vec<_>>();
+        match version {
+            1 => fields.push("0"),
+            VERSION => {}
+            _ => return None,
+        }
+        let [width, height, starting_mines, mines, tiles_left, placed_mines, elapsed, seed, no_guess, practice] =
+            fields[..]
+        else {
+            return None;
+        };
+        let mut saved = SavedGame {
+            width: width.parse().ok()?,
+            height: height.parse().ok()?,
+            starting_mines: starting_mines.parse().ok()?,
+            mines: mines.parse().ok()?,
+            tiles_left: tiles_left.parse().ok()?,
+            placed_mines: format::flag(placed_mines)?,
+            elapsed: elapsed.parse().ok()?,
+            seed: seed.parse().ok()?,
+            no_guess: format::flag(no_guess)?,
+            practice: format::flag(practice)?,
+            board: Vec::new(),
+        };
+        let config = GameConfig::new(saved.width, saved.height, saved.starting_mines);
+        if saved.width == 0 || saved.height == 0 || config.tile_count().is_none() {
+            return None;
+        }
+        for _ in 0..saved.height {
+            let row = lines.next()?;
+            if !row.is_ascii() || row.len() != saved.width * 2 {
+                return None;
+            }
+            for index in (0..row.len()).step_by(2) {
+                let bits = u8::from_str_radix(&row[index..index + 2], 16).ok()?;
+                saved.board.push(Tile::from_bits(bits));
+            }
+        }
+        if lines.next().is_some() || !saved.is_consistent() {
+            return None;
+        }
+        Some(saved)
+    }
+
+    /// Whether the game is in play, the counters agree with the board and every neighbour count with the mines
+    /// around it
+    fn is_consistent(&self) -> bool {
+        if !self.placed_mines || self.tiles_left == 0 {
+            return false;
+        }
+        let (width, height) = (self.width, self.height);
+        let mut mines = 0;
+        let mut flags = 0i64;
+        let mut hidden_safe = 0usize;
+        for y in 0..height {
+            for x in 0..width {
+                let tile = self.board[y * width + x];
+                let around = (x.saturating_sub(1)..(x + 2).min(width))
+                    .flat_map(|nx| {
+                        (y.saturating_sub(1)..(y + 2).min(height)).map(move |ny| (nx, ny))
+                    })
+                    .filter(|&(nx, ny)| (nx, ny) != (x, y) && self.board[ny * width + nx].is_mine())
+                    .count();
+                if tile.neighbouring_mines() as usize != around {
+                    return false;
+                }
+                match tile.state() {
+                    TileState::Revealed if tile.is_mine() => return false,
+                    TileState::Revealed => {}
+                    TileState::Flagged => flags += 1,
+                    TileState::Hidden | TileState::Question => {}
+                }
+                if tile.is_mine() {
+                    mines += 1;
+                } else if !tile.is_revealed() {
+                    hidden_safe += 1;
+                }
+            }
+        }
+        mines == self.starting_mines as usize
+            && self.starting_mines as usize + 9 <= self.board.len()
+            && self.mines == self.starting_mines as i64 - flags
+            && hidden_safe == self.tiles_left as usize
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        paths::write("save.txt", self.to_string())
+    }
+
+    /// Forgets the saved game, which isn't an error if there wasn't one
+    pub fn remove() -> io::Result<()> {
+        match Self::path().map(fs::remove_file) {
+            Some(Err(error)) if error.kind() != io::ErrorKind::NotFound => Err(error),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl std::fmt::Display for SavedGame {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "version {VERSION}")?;
+        writeln!(
+            f,
+            "# width height starting_mines mines tiles_left placed_mines elapsed_millis seed no_guess practice"
+        )?;
+        writeln!(
+            f,
+            "{} {} {} {} {} {} {} {} {} {}",
+            self.width,
+            self.height,
+            self.starting_mines,
+            self.mines,
+            self.tiles_left,
+            self.placed_mines as u8,
+            self.elapsed,
+            self.seed,
+            self.no_guess as u8,
+            self.practice as u8
+        )?;
+        writeln!(
+            f,
+            "# tiles: bit 0 flagged, 1 revealed, 2 mine, 3 question mark, 4-7 neighbouring mines"
+        )?;
+        for row in self.board.chunks(self.width) {
+            let mut line = String::with_capacity(self.width * 2);
+            for tile in row {
+                let _ = write!(line, "{:02x}", tile.bits());
+            }
+            writeln!(f, "{line}")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_games_in_play_are_resumed() {
+        let mut data = Data::with_seed(10, 9, 9, 7);
+        let fresh = SavedGame::new(&data);
+        assert_eq!(SavedGame::parse(&fresh.to_string()), None);
+
+        data.reveal(4, 4);
+        let playing = SavedGame::new(&data);
+        assert_eq!(SavedGame::parse(&playing.to_string()), Some(playing));
+
+        let won = SavedGame::new(&data.solution().unwrap());
+        assert_eq!(won.tiles_left, 0);
+        assert_eq!(SavedGame::parse(&won.to_string()), None);
+    }
+}
@@ -1 +1 @@
-struct I{@location(0)a:vec3<f32>,@location(1)b:vec2<f32>};struct O{@builtin(position)a:vec4<f32>,@location(0)b:vec2<f32>};@vertex fn v(b:I)->O{var a:O;a.b=b.b;a.a=vec4<f32>(b.a, 1.0);return a;}@group(0)@binding(0)var t:texture_2d<f32>;@group(0)@binding(1)var s:sampler;@fragment fn f(a:O)->@location(0)vec4<f32>{return textureSample(t,s,a.b);}
\ No newline at end of file
+struct I{@location(0)a:vec3<f32>,@location(1)b:vec2<f32>,@location(2)c:vec4<f32>};struct O{@builtin(position)a:vec4<f32>,@location(0)b:vec2<f32>,@location(1)c:vec4<f32>};@vertex fn v(b:I)->O{var a:O;a.b=b.b;a.c=b.c;a.a=vec4<f32>(b.a, 1.0);return a;}@group(0)@binding(0)var t:texture_2d<f32>;@group(0)@binding(1)var s:sampler;@fragment fn f(a:O)->@location(0)vec4<f32>{return textureSample(t,s,a.b)*a.c;}
\ No newline at end of file
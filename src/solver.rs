@@ -0,0 +1,783 @@
+//! Deductions that follow from the revealed numbers alone, never from where the mines actually are
+
+use crate::tile::TileContent;
+use crate::Data;
+
+#[derive(Copy, Clone, PartialEq, Eq)]
+enum Cell {
+    Unknown,
+    Mine,
+    /// Known to be safe, with its number once it has been revealed
+    Safe(Option<u8>),
+}
+
+/// Mines still missing among a set of unknown cells
+struct Constraint {
+    cells: Vec<usize>,
+    mines: usize,
+}
+
+pub struct Solver {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+    /// Mines on the whole board
+    mines: usize,
+}
+
+impl Solver {
+    /// Everything the player can see, flags are ignored as they may be wrong
+    pub fn new(data: &Data) -> Solver {
+        let mut cells = Vec::with_capacity(data.width() * data.height());
+        for y in 0..data.height() {
+            for x in 0..data.width() {
+                let tile = data.get(x, y);
+                cells.push(match tile.content() {
+                    TileContent::Number(count) if tile.is_revealed() => Cell::Safe(Some(count)),
+                    _ => Cell::Unknown,
+                });
+            }
+        }
+        Solver {
+            width: data.width(),
+            height: data.height(),
+            cells,
+            mines: data.starting_mines() as usize,
+        }
+    }
+
+    /// Whether a player who reveals `(x, y)` first could clear the entire board without guessing
+    pub fn is_solvable(data: &Data, x: usize, y: usize) -> bool {
+        Self::play(data, x, y).is_some_and(|solver| !solver.cells.contains(&Cell::Unknown))
+    }
+
+    /// Plays the board from `(x, y)`, revealing everything the deductions prove safe, `None` if `(x, y)` is a mine
+    ///
+    /// Whatever is still [`undecided`](Solver::undecided) afterwards takes a guess to clear.
+    pub(crate) fn play(data: &Data, x: usize, y: usize) -> Option<Solver> {
+        let mut solver = Solver {
+            width: data.width(),
+            height: data.height(),
+            cells: vec![Cell::Unknown; data.width() * data.height()],
+            mines: data.starting_mines() as usize,
+        };
+        if solver.reveal(data, x, y) {
+            return None;
+        }
+        loop {
+            solver.solve();
+            let mut progressed = false;
+            for index in 0..solver.cells.len() {
+                if solver.cells[index] == Cell::Safe(None) {
+                    solver.reveal(data, index % solver.width, index / solver.width);
+                    progressed = true;
+                }
+            }
+            if !progressed {
+                return Some(solver);
+            }
+        }
+    }
+
+    /// Tiles that are neither proven safe nor proven mines
+    pub(crate) fn undecided(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.positions(Cell::Unknown)
+    }
+
+    /// Whether a revealed number touches the tile
+    pub(crate) fn next_to_number(&self, x: usize, y: usize) -> bool {
+        self.neighbours(y * self.width + x)
+            .any(|neighbour| matches!(self.cells[neighbour], Cell::Safe(Some(_))))
+    }
+
+    /// Tiles that can't be a mine but haven't been revealed yet
+    pub fn safe(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.positions(Cell::Safe(None))
+    }
+
+    /// Tiles that must be a mine
+    pub fn mines(&self) -> impl Iterator<Item = (usize, usize)> + '_ {
+        self.positions(Cell::Mine)
+    }
+
+    /// Applies every rule until nothing new can be deduced
+    pub fn solve(&mut self) {
+        while self.apply_single() || self.apply_pairs() || self.apply_global() {}
+    }
+
+    /// The exact chance of each tile being a mine, row by row, assuming every layout consistent with the revealed
+    /// numbers and the mine count is equally likely
+    ///
+    /// Every arrangement of mines along the numbers is enumerated, weighted by the ways the remaining mines fit into
+    /// the tiles no number touches. Returns `None` if there are too many arrangements to go through.
+    pub fn probabilities(&self) -> Option<Vec<f32>> {
+        self.probabilities_within(ENUMERATION_BUDGET)
+    }
+
+    /// [`probabilities`](Solver::probabilities) giving up after `budget` search steps
+    fn probabilities_within(&self, mut budget: usize) -> Option<Vec<f32>> {
+        let constraints = self.constraints();
+        let known = self
+            .cells
+            .iter()
+            .filter(|&&cell| cell == Cell::Mine)
+            .count();
+        let left = self.mines.checked_sub(known)?;
+
+        let mut component_of = vec![usize::MAX; self.cells.len()];
+        let mut components = Vec::<Component>::new();
+        for constraint in &constraints {
+            let mut merged = constraint
+                .cells
+                .iter()
+                .filter_map(|&cell| {
+                    Some(component_of[cell]).filter(|&component| component != usize::MAX)
+                })
+                .collect::<Vec<_>>();
+            merged.sort_unstable();
+            merged.dedup();
+            let target = match merged.first() {
+                Some(&target) => target,
+                None => {
+                    components.push(Component::default());
+                    components.len() - 1
+                }
+            };
+            for &other in merged.iter().skip(1).rev() {
+                let other = std::mem::take(&mut components[other]);
+                other
+                    .cells
+                    .iter()
+                    .for_each(|&cell| component_of[cell] = target);
+                components[target].absorb(other);
+            }
+            let mut local = vec![];
+            for &cell in &constraint.cells {
+                if component_of[cell] != target {
+                    component_of[cell] = target;
+                    components[target].cells.push(cell);
+                }
+                local.push(
+                    components[target]
+                        .cells
+                        .iter()
+                        .position(|&other| other == cell)
+                        .unwrap_or_default(),
+                );
+            }
+            components[target]
+                .constraints
+                .push((local, constraint.mines));
+        }
+        components.retain(|component| !component.cells.is_empty());
+
+        let interior = self
+            .cells
+            .iter()
+            .enumerate()
+            .filter(|&(index, &cell)| cell == Cell::Unknown && component_of[index] == usize::MAX)
+            .count();
+
+        let mut arrangements = Vec::with_capacity(components.len());
+        for component in &components {
+            arrangements.push(component.enumerate(&mut budget)?);
+        }
+
+        // ln C(interior, r) for every r, shifted so the largest weight is 1
+        let mut ln_weights = vec![f64::NEG_INFINITY; left + 1];
+        let mut ln_choose = 0.0;
+        for (r, weight) in ln_weights
+            .iter_mut()
+            .enumerate()
+            .take(interior.min(left) + 1)
+        {
+            if r > 0 {
+                ln_choose += ((interior - r + 1) as f64).ln() - (r as f64).ln();
+            }
+            *weight = ln_choose;
+        }
+        let max = ln_weights.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        let weight = |frontier: usize| -> f64 {
+            left.checked_sub(frontier)
+                .map_or(0.0, |r| (ln_weights[r] - max).exp())
+        };
+
+        let all = arrangements.iter().fold(vec![1.0], |total, arrangement| {
+            convolve(&total, &arrangement.counts)
+        });
+        let total = all
+            .iter()
+            .enumerate()
+            .map(|(mines, &count)| count * weight(mines))
+            .sum::<f64>();
+        if total <= 0.0 {
+            return None;
+        }
+
+        let mut probabilities = self
+            .cells
+            .iter()
+            .map(|&cell| if cell == Cell::Mine { 1.0 } else { 0.0 })
+            .collect::<Vec<f32>>();
+        if interior > 0 {
+            let expected = all
+                .iter()
+                .enumerate()
+                .map(|(mines, &count)| count * weight(mines) * left.saturating_sub(mines) as f64)
+                .sum::<f64>();
+            let chance = (expected / total / interior as f64) as f32;
+            for (index, probability) in probabilities.iter_mut().enumerate() {
+                if self.cells[index] == Cell::Unknown && component_of[index] == usize::MAX {
+                    *probability = chance;
+                }
+            }
+        }
+        for (index, (component, arrangement)) in components.iter().zip(&arrangements).enumerate() {
+            let others = arrangements
+                .iter()
+                .enumerate()
+                .filter(|&(other, _)| other != index)
+                .fold(vec![1.0], |total, (_, arrangement)| {
+                    convolve(&total, &arrangement.counts)
+                });
+            for (local, &cell) in component.cells.iter().enumerate() {
+                let mut sum = 0.0;
+                for (mines, tallies) in arrangement.tallies.iter().enumerate() {
+                    for (other_mines, &count) in others.iter().enumerate() {
+                        sum += tallies[local] * count * weight(mines + other_mines);
+                    }
+                }
+                probabilities[cell] = (sum / total) as f32;
+            }
+        }
+        Some(probabilities)
+    }
+
+    fn positions(&self, cell: Cell) -> impl Iterator<Item = (usize, usize)> + '_ {
+        let width = self.width;
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(move |(_, &other)| other == cell)
+            .map(move |(index, _)| (index % width, index / width))
+    }
+
+    /// Peeks at the real board the way a click would, returns whether it was a mine
+    fn reveal(&mut self, data: &Data, x: usize, y: usize) -> bool {
+        let index = y * self.width + x;
+        if matches!(self.cells[index], Cell::Safe(Some(_))) {
+            return false;
+        }
+        let TileContent::Number(count) = data.get(x, y).content() else {
+            return true;
+        };
+        self.cells[index] = Cell::Safe(Some(count));
+        // spreads from empty cells with a list instead of recursing, like Data::click
+        let mut empty = if count == 0 { vec![(x, y)] } else { Vec::new() };
+        while let Some((x, y)) = empty.pop() {
+            for (x, y) in data.neighbours(x, y) {
+                let index = y * self.width + x;
+                if matches!(self.cells[index], Cell::Safe(Some(_))) {
+                    continue;
+                }
+                let count = data.get(x, y).neighbouring_mines();
+                self.cells[index] = Cell::Safe(Some(count));
+                if count == 0 {
+                    empty.push((x, y));
+                }
+            }
+        }
+        false
+    }
+
+    fn neighbours(&self, index: usize) -> impl Iterator<Item = usize> {
+        let width = self.width;
+        let height = self.height;
+        let x = index % width;
+        let y = index / width;
+        [
+            (x.wrapping_sub(1), y.wrapping_sub(1)),
+            (x, y.wrapping_sub(1)),
+            (x + 1, y.wrapping_sub(1)),
+            (x.wrapping_sub(1), y),
+            (x + 1, y),
+            (x.wrapping_sub(1), y + 1),
+            (x, y + 1),
+            (x + 1, y + 1),
+        ]
+        .into_iter()
+        .filter(move |&(x, y)| x < width && y < height)
+        .map(move |(x, y)| y * width + x)
+    }
+
+    /// One constraint per revealed number that still touches unknown cells
+    fn constraints(&self) -> Vec<Constraint> {
+        let mut constraints = vec![];
+        for (index, &cell) in self.cells.iter().enumerate() {
+            let Cell::Safe(Some(count)) = cell else {
+                continue;
+            };
+            let mut cells = vec![];
+            let mut mines = count as usize;
+            for neighbour in self.neighbours(index) {
+                match self.cells[neighbour] {
+                    Cell::Unknown => cells.push(neighbour),
+                    Cell::Mine => mines -= 1,
+                    Cell::Safe(_) => {}
+                }
+            }
+            if !cells.is_empty() {
+                constraints.push(Constraint { cells, mines });
+            }
+        }
+        constraints
+    }
+
+    /// Marks every cell in `cells` as a mine if they all have to be one, or as safe if none can be
+    fn resolve(&mut self, cells: &[usize], mines: usize) -> bool {
+        let cell = if mines == 0 {
+            Cell::Safe(None)
+        } else if mines == cells.len() {
+            Cell::Mine
+        } else {
+            return false;
+        };
+        let mut changed = false;
+        for &index in cells {
+            if self.cells[index] == Cell::Unknown {
+                self.cells[index] = cell;
+                changed = true;
+            }
+        }
+        changed
+    }
+
+    /// A number with as many unknown neighbours as missing mines, or none missing
+    fn apply_single(&mut self) -> bool {
+        let mut changed = false;
+        for constraint in self.constraints() {
+            changed |= self.resolve(&constraint.cells, constraint.mines);
+        }
+        changed
+    }
+
+    /// Two numbers sharing unknown cells bound how many mines the shared cells hold, which can settle the cells
+    /// only one of them touches, the classic case being one number's cells all belonging to the other
+    fn apply_pairs(&mut self) -> bool {
+        let constraints = self.constraints();
+        let mut touching = vec![Vec::<usize>::new(); self.cells.len()];
+        for (index, constraint) in constraints.iter().enumerate() {
+            for &cell in &constraint.cells {
+                touching[cell].push(index);
+            }
+        }
+
+        let mut changed = false;
+        let mut seen = vec![usize::MAX; constraints.len()];
+        for (index, a) in constraints.iter().enumerate() {
+            for &cell in &a.cells {
+                for &other in &touching[cell] {
+                    if other <= index || seen[other] == index {
+                        continue;
+                    }
+                    seen[other] = index;
+                    let b = &constraints[other];
+                    let only_a = a
+                        .cells
+                        .iter()
+                        .copied()
+                        .filter(|cell| !b.cells.contains(cell))
+                        .collect::<Vec<_>>();
+                    let only_b = b
+                        .cells
+                        .iter()
+                        .copied()
+                        .filter(|cell| !a.cells.contains(cell))
+                        .collect::<Vec<_>>();
+                    let shared = a.cells.len() - only_a.len();
+                    let least = a
+                        .mines
+                        .saturating_sub(only_a.len())
+                        .max(b.mines.saturating_sub(only_b.len()));
+                    let most = a.mines.min(b.mines).min(shared);
+                    for (only, mines) in [(&only_a, a.mines), (&only_b, b.mines)] {
+                        if mines - most == only.len() {
+                            changed |= self.resolve(only, only.len());
+                        } else if mines == least {
+                            changed |= self.resolve(only, 0);
+                        }
+                    }
+                }
+            }
+        }
+        changed
+    }
+
+    /// Numbers that share no cells account for an exact amount of the mines left, so whatever is left over
+    /// belongs to the cells no number touches
+    fn apply_global(&mut self) -> bool {
+        let mut left = self.mines
+            - self
+                .cells
+                .iter()
+                .filter(|&&cell| cell == Cell::Mine)
+                .count();
+        let mut claimed = vec![false; self.cells.len()];
+        for constraint in self.constraints() {
+            if constraint.cells.iter().all(|&cell| !claimed[cell]) {
+                constraint
+                    .cells
+                    .iter()
+                    .for_each(|&cell| claimed[cell] = true);
+                left -= constraint.mines;
+            }
+        }
+        let rest = (0..self.cells.len())
+            .filter(|&index| self.cells[index] == Cell::Unknown && !claimed[index])
+            .collect::<Vec<_>>();
+        !rest.is_empty() && self.resolve(&rest, left)
+    }
+}
+
+/// Search steps allowed across all components before giving up on exact probabilities
+const ENUMERATION_BUDGET: usize = 1 << 22;
+
+/// Numbers linked together by shared unknown cells, independent of every other component
+#[derive(Default)]
+struct Component {
+    cells: Vec<usize>,
+    /// Constraints over indices into `cells`
+    constraints: Vec<(Vec<usize>, usize)>,
+}
+
+/// Valid arrangements of a component, by how many mines they use
+struct Arrangements {
+    counts: Vec<f64>,
+    /// How many of the arrangements with that many mines have a mine on each cell
+    tallies: Vec<Vec<f64>>,
+}
+
+impl Component {
+    fn absorb(&mut self, other: Component) {
+        let offset = self.cells.len();
+        self.cells.extend(other.cells);
+        self.constraints.extend(
+            other.constraints.into_iter().map(|(cells, mines)| {
+                (cells.into_iter().map(|cell| cell + offset).collect(), mines)
+            }),
+        );
+    }
+
+    fn enumerate(&self, budget: &mut usize) -> Option<Arrangements> {
+        let mut touching = vec![Vec::new(); self.cells.len()];
+        for (index, (cells, _)) in self.constraints.iter().enumerate() {
+            for &cell in cells {
+                touching[cell].push(index);
+            }
+        }
+        let mut search = Search {
+            touching,
+            placed: vec![0; self.constraints.len()],
+            open: self
+                .constraints
+                .iter()
+                .map(|(cells, _)| cells.len())
+                .collect(),
+            needed: self.constraints.iter().map(|&(_, mines)| mines).collect(),
+            mines: vec![false; self.cells.len()],
+            arrangements: Arrangements {
+                counts: vec![0.0; self.cells.len() + 1],
+                tallies: vec![vec![0.0; self.cells.len()]; self.cells.len() + 1],
+            },
+            budget,
+        };
+        search.visit(0, 0).then_some(search.arrangements)
+    }
+}
+
+struct Search<'a> {
+    touching: Vec<Vec<usize>>,
+    placed: Vec<usize>,
+    /// Cells of each constraint that haven't been decided yet
+    open: Vec<usize>,
+    needed: Vec<usize>,
+    mines: Vec<bool>,
+    arrangements: Arrangements,
+    budget: &'a mut usize,
+}
+
+impl Search<'_> {
+    /// Tries both options for `cell` and everything after it, returns false once out of budget
+    fn visit(&mut self, cell: usize, mines: usize) -> bool {
+        if *self.budget == 0 {
+            return false;
+        }
+        *self.budget -= 1;
+
+        if cell == self.mines.len() {
+            self.arrangements.counts[mines] += 1.0;
+            for (tally, &mine) in self.arrangements.tallies[mines].iter_mut().zip(&self.mines) {
+                if mine {
+                    *tally += 1.0;
+                }
+            }
+            return true;
+        }
+
+        for mine in [false, true] {
+            let fits = self.touching[cell].iter().all(|&constraint| {
+                let placed = self.placed[constraint] + mine as usize;
+                // not over the count, and the cells still open after this one can make up the rest
+                placed <= self.needed[constraint]
+                    && placed + self.open[constraint] > self.needed[constraint]
+            });
+            if !fits {
+                continue;
+            }
+            for &constraint in &self.touching[cell] {
+                self.placed[constraint] += mine as usize;
+                self.open[constraint] -= 1;
+            }
+            self.mines[cell] = mine;
+            let finished = self.visit(cell + 1, mines + mine as usize);
+            self.mines[cell] = false;
+            for &constraint in &self.touching[cell] {
+                self.placed[constraint] -= mine as usize;
+                self.open[constraint] += 1;
+            }
+            if !finished {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+fn convolve(a: &[f64], b: &[f64]) -> Vec<f64> {
+    let mut out = vec![0.0; a.len() + b.len() - 1];
+    for (i, &a) in a.iter().enumerate() {
+        for (j, &b) in b.iter().enumerate() {
+            out[i + j] += a * b;
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A solver for a board drawn row by row, `o` a revealed tile, `.` a hidden safe one and `*` a hidden mine,
+    /// along with where the mines really are
+    fn board(rows: &[&str]) -> (Solver, Vec<bool>) {
+        let width = rows[0].len();
+        let truth = rows
+            .iter()
+            .flat_map(|row| row.chars())
+            .map(|tile| tile == '*')
+            .collect::<Vec<_>>();
+        let mut solver = Solver {
+            width,
+            height: rows.len(),
+            cells: vec![Cell::Unknown; truth.len()],
+            mines: truth.iter().filter(|&&mine| mine).count(),
+        };
+        for (index, tile) in rows.iter().flat_map(|row| row.chars()).enumerate() {
+            if tile == 'o' {
+                let count = solver
+                    .neighbours(index)
+                    .filter(|&neighbour| truth[neighbour])
+                    .count();
+                solver.cells[index] = Cell::Safe(Some(count as u8));
+            }
+        }
+        (solver, truth)
+    }
+
+    type Tiles = Vec<(usize, usize)>;
+
+    /// The tiles the solver has found safe and the ones it has found to be mines, checked against the real mines
+    fn decided(solver: &Solver, truth: &[bool]) -> (Tiles, Tiles) {
+        let safe = solver.safe().collect::<Vec<_>>();
+        let mines = solver.mines().collect::<Vec<_>>();
+        for &(x, y) in &safe {
+            assert!(
+                !truth[y * solver.width + x],
+                "the mine at ({x}, {y}) was marked safe"
+            );
+        }
+        for &(x, y) in &mines {
+            assert!(
+                truth[y * solver.width + x],
+                "the safe tile at ({x}, {y}) was marked a mine"
+            );
+        }
+        (safe, mines)
+    }
+
+    #[test]
+    fn single_numbers_settle_their_own_neighbours() {
+        let (mut solver, truth) = board(&["oooo", "*..."]);
+        assert!(solver.apply_single());
+        assert_eq!(
+            decided(&solver, &truth),
+            (vec![(1, 1), (2, 1), (3, 1)], vec![])
+        );
+        // with its other neighbour safe the corner 1 has found its mine
+        assert!(solver.apply_single());
+        assert_eq!(
+            decided(&solver, &truth),
+            (vec![(1, 1), (2, 1), (3, 1)], vec![(0, 1)])
+        );
+        assert!(!solver.apply_single());
+    }
+
+    #[test]
+    fn overlapping_numbers_settle_what_they_dont_share() {
+        // each 1 on the edge has its cells inside the 1 next to it
+        let (mut solver, truth) = board(&["oooo", "*..*"]);
+        assert!(!solver.apply_single());
+        assert!(solver.apply_pairs());
+        assert_eq!(decided(&solver, &truth), (vec![(1, 1), (2, 1)], vec![]));
+
+        // 1-2-1, the 2 needs a mine outside of either 1
+        let (mut solver, truth) = board(&["ooo", "*.*"]);
+        assert!(!solver.apply_single());
+        assert!(solver.apply_pairs());
+        assert_eq!(decided(&solver, &truth), (vec![], vec![(0, 1), (2, 1)]));
+
+        // numbers that don't pin each other down leave everything undecided
+        let (mut solver, truth) = board(&["oo", "*."]);
+        assert!(!solver.apply_pairs());
+        assert_eq!(decided(&solver, &truth), (vec![], vec![]));
+    }
+
+    #[test]
+    fn the_mine_count_settles_tiles_no_number_touches() {
+        let (mut solver, truth) = board(&["*o..."]);
+        assert!(!solver.apply_single() && !solver.apply_pairs());
+        assert!(solver.apply_global());
+        assert_eq!(decided(&solver, &truth), (vec![(3, 0), (4, 0)], vec![]));
+
+        let (mut solver, truth) = board(&["*o.**"]);
+        assert!(solver.apply_global());
+        assert_eq!(decided(&solver, &truth), (vec![], vec![(3, 0), (4, 0)]));
+
+        // mines that could be on either side of the number leave the rest open
+        let (mut solver, truth) = board(&["*o.*."]);
+        assert!(!solver.apply_global());
+        assert_eq!(decided(&solver, &truth), (vec![], vec![]));
+    }
+
+    #[test]
+    fn deductions_on_real_games_are_never_wrong() {
+        for seed in 0..40 {
+            let mut data = Data::with_seed(10 + seed as u32 % 30, 12, 10, seed);
+            data.reveal(6, 5);
+            let mut solver = Solver::new(&data);
+            solver.solve();
+            for (x, y) in solver.safe() {
+                assert!(
+                    !data.get(x, y).is_mine(),
+                    "seed {seed} marked the mine at ({x}, {y}) safe"
+                );
+            }
+            for (x, y) in solver.mines() {
+                assert!(
+                    data.get(x, y).is_mine(),
+                    "seed {seed} marked ({x}, {y}) a mine"
+                );
+            }
+        }
+    }
+
+    /// Every way the mines left could be spread over the unknown tiles that agrees with the numbers, counted one
+    /// by one
+    fn brute_force(solver: &Solver) -> Vec<f32> {
+        let unknown = (0..solver.cells.len())
+            .filter(|&index| solver.cells[index] == Cell::Unknown)
+            .collect::<Vec<_>>();
+        let known = solver.mines().count();
+        let mut mines = vec![0u64; solver.cells.len()];
+        let mut total = 0u64;
+        for layout in 0u32..1 << unknown.len() {
+            if layout.count_ones() as usize + known != solver.mines {
+                continue;
+            }
+            let is_mine = |index: usize| match unknown.binary_search(&index) {
+                Ok(bit) => layout & 1 << bit != 0,
+                Err(_) => solver.cells[index] == Cell::Mine,
+            };
+            let fits = solver
+                .cells
+                .iter()
+                .enumerate()
+                .all(|(index, &cell)| match cell {
+                    Cell::Safe(Some(count)) => {
+                        solver
+                            .neighbours(index)
+                            .filter(|&neighbour| is_mine(neighbour))
+                            .count()
+                            == count as usize
+                    }
+                    _ => true,
+                });
+            if fits {
+                total += 1;
+                for (index, count) in mines.iter_mut().enumerate() {
+                    *count += is_mine(index) as u64;
+                }
+            }
+        }
+        mines
+            .into_iter()
+            .map(|count| (count as f64 / total as f64) as f32)
+            .collect()
+    }
+
+    fn assert_close(probabilities: &[f32], expected: &[f32]) {
+        for (index, (&probability, &expected)) in probabilities.iter().zip(expected).enumerate() {
+            assert!(
+                (probability - expected).abs() < 1e-5,
+                "tile {index} is a mine {probability} of the time rather than {expected}"
+            );
+        }
+    }
+
+    #[test]
+    fn probabilities_match_counting_every_layout() {
+        // separate components, a tile no number touches and mines already found
+        for rows in [
+            &["*o.**"][..],
+            &["ooo", "*.*"],
+            &["oooo", "*..*", "...."],
+            &[".*o..", "o.o*.", "..o.*"],
+        ] {
+            let (solver, _) = board(rows);
+            assert_close(&solver.probabilities().unwrap(), &brute_force(&solver));
+        }
+
+        let mut boards = 0;
+        for seed in 0..200 {
+            let mut data = Data::with_seed(4 + seed as u32 % 5, 6, 5, seed);
+            data.reveal(seed as usize % 6, 2);
+            let mut solver = Solver::new(&data);
+            if seed % 2 == 0 {
+                solver.solve();
+            }
+            if data.status() != crate::GameStatus::Playing || solver.undecided().count() > 16 {
+                continue;
+            }
+            boards += 1;
+            assert_close(&solver.probabilities().unwrap(), &brute_force(&solver));
+        }
+        assert!(boards > 50, "only {boards} boards were compared");
+    }
+
+    #[test]
+    fn probabilities_give_up_past_the_budget() {
+        let (solver, _) = board(&["oooo", "*..*", "...."]);
+        assert!(solver.probabilities_within(1_000).is_some());
+        assert_eq!(solver.probabilities_within(5), None);
+    }
+}
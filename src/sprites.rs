@@ -0,0 +1,242 @@
+//! Where every sprite sits in the built-in atlases, by the names the renderer draws them with and theme packs
+//! use to provide their own
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Sprite {
+    pub name: &'static str,
+    /// Top left corner in the atlas
+    pub uv: (u32, u32),
+    pub size: (u32, u32),
+}
+
+const fn sprite(name: &'static str, uv: (u32, u32), size: (u32, u32)) -> Sprite {
+    Sprite { name, uv, size }
+}
+
+/// The sprites in an atlas of `size`
+pub struct Layout {
+    pub size: (u32, u32),
+    pub sprites: &'static [Sprite],
+}
+
+impl Layout {
+    pub fn find(&self, name: &str) -> Option<Sprite> {
+        self.sprites
+            .iter()
+            .find(|sprite| sprite.name == name)
+            .copied()
+    }
+
+    /// Panics if there's no sprite called `name`, the names the renderer uses are all checked by the tests
+    pub fn get(&self, name: &str) -> Sprite {
+        self.find(name)
+            .unwrap_or_else(|| panic!("there's no sprite called {name}"))
+    }
+}
+
+/// Both built-in atlases share a layout, tiles come first as they're looked up the most
+pub const BUILT_IN: Layout = Layout {
+    size: (256, 256),
+    sprites: &[
+        // tiles
+        sprite("hidden", (48, 16), (16, 16)),
+        sprite("hidden_hover", (48, 0), (16, 16)),
+        sprite("pressed", (16, 16), (16, 16)),
+        sprite("flag", (32, 16), (16, 16)),
+        sprite("flag_hover", (32, 0), (16, 16)),
+        sprite("question", (131, 48), (16, 16)),
+        sprite("question_hover", (131, 64), (16, 16)),
+        sprite("mine", (16, 0), (16, 16)),
+        sprite("mine_exploded", (0, 16), (16, 16)),
+        sprite("wrong_flag", (0, 0), (16, 16)),
+        sprite("number_1", (48, 48), (16, 16)),
+        sprite("number_2", (32, 48), (16, 16)),
+        sprite("number_3", (16, 48), (16, 16)),
+        sprite("number_4", (0, 48), (16, 16)),
+        sprite("number_5", (48, 32), (16, 16)),
+        sprite("number_6", (32, 32), (16, 16)),
+        sprite("number_7", (16, 32), (16, 16)),
+        sprite("number_8", (0, 32), (16, 16)),
+        // overlays on top of tiles
+        sprite("sheen", (131, 0), (16, 16)),
+        sprite("hint", (131, 16), (16, 16)),
+        sprite("white", (131, 32), (16, 16)),
+        // counters, the frame is drawn whole above the board and in pieces around panel counters
+        sprite("counter", (64, 0), (41, 25)),
+        sprite("counter_left", (64, 0), (1, 25)),
+        sprite("counter_right", (104, 0), (1, 25)),
+        sprite("counter_top", (65, 0), (13, 1)),
+        sprite("counter_bottom", (65, 24), (13, 1)),
+        sprite("digit_blank", (65, 1), (13, 23)),
+        sprite("digit_minus", (0, 110), (13, 23)),
+        sprite("digit_0", (52, 87), (13, 23)),
+        sprite("digit_1", (39, 87), (13, 23)),
+        sprite("digit_2", (26, 87), (13, 23)),
+        sprite("digit_3", (13, 87), (13, 23)),
+        sprite("digit_4", (0, 87), (13, 23)),
+        sprite("digit_5", (52, 64), (13, 23)),
+        sprite("digit_6", (39, 64), (13, 23)),
+        sprite("digit_7", (26, 64), (13, 23)),
+        sprite("digit_8", (13, 64), (13, 23)),
+        sprite("digit_9", (0, 64), (13, 23)),
+        sprite("dot", (173, 26), (2, 2)),
+        // faces
+        sprite("face", (105, 26), (26, 26)),
+        sprite("face_pressed", (105, 78), (26, 26)),
+        sprite("face_won", (105, 0), (26, 26)),
+        sprite("face_lost", (105, 52), (26, 26)),
+        // window borders, the edges are tiled along the sides
+        sprite("border_top", (81, 25), (16, 55)),
+        sprite("border_bottom", (81, 96), (16, 8)),
+        sprite("border_left", (69, 80), (12, 16)),
+        sprite("border_right", (97, 80), (8, 16)),
+        sprite("corner_top_left", (69, 25), (12, 55)),
+        sprite("corner_top_right", (97, 25), (8, 55)),
+        sprite("corner_bottom_left", (69, 96), (12, 8)),
+        sprite("corner_bottom_right", (97, 96), (8, 8)),
+        // panel icons
+        sprite("icon_wins", (147, 0), (26, 26)),
+        sprite("icon_losses", (173, 0), (26, 26)),
+        sprite("icon_streak", (199, 0), (26, 26)),
+        sprite("icon_best_streak", (225, 0), (26, 26)),
+        sprite("icon_best_time", (147, 26), (26, 26)),
+        sprite("icon_width", (199, 26), (26, 26)),
+        sprite("icon_height", (225, 26), (26, 26)),
+        sprite("icon_mines", (147, 52), (26, 26)),
+        sprite("icon_density", (173, 52), (26, 26)),
+    ],
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::custom_game::CustomGame;
+    use crate::vertex_buffer_builder::VertexBufferBuilder;
+    use crate::{render, Clock, Ui};
+    use minesweeper_rs::Data;
+    use std::collections::HashSet;
+    use winit::dpi::PhysicalSize;
+
+    #[test]
+    fn every_sprite_fits_in_the_atlas() {
+        let mut names = HashSet::new();
+        for sprite in BUILT_IN.sprites {
+            assert!(
+                names.insert(sprite.name),
+                "{} is in the layout twice",
+                sprite.name
+            );
+            assert!(
+                sprite.size.0 > 0 && sprite.size.1 > 0,
+                "{} is empty",
+                sprite.name
+            );
+            assert!(
+                sprite.uv.0 + sprite.size.0 <= BUILT_IN.size.0
+                    && sprite.uv.1 + sprite.size.1 <= BUILT_IN.size.1,
+                "{} goes past the edge of the atlas",
+                sprite.name
+            );
+        }
+    }
+
+    /// A game with every kind of tile on it, flags, question marks and numbers
+    fn playing() -> Data {
+        let mut data = Data::with_seed(40, 16, 16, 2);
+        data.set_question_marks(true);
+        data.reveal(8, 8);
+        for x in 0..16 {
+            data.flag(x, 0);
+        }
+        data.flag(0, 1);
+        data.flag(0, 1);
+        data
+    }
+
+    /// Draws every kind of screen there is, any name missing from the layout panics and anything drawn from outside
+    /// the atlas would wrap around to some other sprite
+    #[test]
+    fn rendered_sprites_fit_in_the_atlas() {
+        let fresh = || Data::with_seed(10, 9, 9, 1);
+        let mut lost = playing();
+        let mine = (0..256)
+            .map(|index| (index % 16, index / 16))
+            .find(|&(x, y)| lost.get(x, y).is_mine() && !lost.get(x, y).is_flagged() && y > 1)
+            .unwrap();
+        lost.reveal(mine.0, mine.1);
+        let scenes = [
+            (fresh(), Ui::default()),
+            (
+                fresh(),
+                Ui {
+                    mouse_x: 90.0,
+                    mouse_y: 25.0,
+                    mouse_held: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                fresh(),
+                Ui {
+                    resizing: Some((300, 400)),
+                    ..Default::default()
+                },
+            ),
+            (
+                fresh(),
+                Ui {
+                    show_stats: true,
+                    ..Default::default()
+                },
+            ),
+            (
+                fresh(),
+                Ui {
+                    custom: Some(CustomGame::new(&fresh())),
+                    ..Default::default()
+                },
+            ),
+            (
+                playing(),
+                Ui {
+                    hint: Some((15, 15)),
+                    probabilities: Some(vec![0.5; 256]),
+                    mouse_x: 20.0,
+                    mouse_y: 60.0,
+                    ..Default::default()
+                },
+            ),
+            (
+                playing(),
+                Ui {
+                    mouse_x: 20.0,
+                    mouse_y: 76.0,
+                    middle_held: true,
+                    ..Default::default()
+                },
+            ),
+            (lost, Ui::default()),
+            (playing().solution().unwrap(), Ui::default()),
+        ];
+
+        for (data, ui) in scenes {
+            let size = PhysicalSize::new(
+                20 + 16 * data.width() as u32,
+                63 + 16 * data.height() as u32,
+            );
+            let mut builder = VertexBufferBuilder::new(&size, BUILT_IN.size.0, BUILT_IN.size.1);
+            render(&mut builder, &data, &ui, Clock::now(&data));
+            // texture coordinates are the third and fourth floats of each vertex
+            for vertex in builder.vertices().chunks_exact(24) {
+                for offset in [12, 16] {
+                    let coordinate =
+                        f32::from_ne_bytes(vertex[offset..offset + 4].try_into().unwrap());
+                    assert!(
+                        (0.0..=1.0).contains(&coordinate),
+                        "drew from {coordinate} of the atlas"
+                    );
+                }
+            }
+        }
+    }
+}
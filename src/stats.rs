@@ -0,0 +1,274 @@
+//! Wins, losses, streaks and best times for every board configuration played
+//!
+//! Stored as `stats.txt` in [`config_dir`], one line per configuration after a version line:
+//!
+//! ```text
+//! version 2
+//! # width height mines wins losses streak best_streak best_millis
+//! 9 9 10 12 4 3 5 41274
+//! 30 16 99 0 2 0 0 -
+//! ```
+//!
+//! Version 1 files stored whole seconds and are converted when read.
+
+use crate::format;
+use crate::paths::{self, config_dir};
+use crate::{Data, GameStatus};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+const VERSION: u32 = 2;
+
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct Record {
+    pub wins: u32,
+    pub losses: u32,
+    /// Wins in a row, reset by a loss
+    pub streak: u32,
+    pub best_streak: u32,
+    /// Milliseconds taken by the fastest win
+    pub best_time: Option<u64>,
+}
+
+/// Boards are told apart by width, height and mine count
+pub type Board = (usize, usize, u32);
+
+#[derive(Default)]
+pub struct Stats {
+    records: BTreeMap<Board, Record>,
+}
+
+impl Stats {
+    pub fn path() -> Option<PathBuf> {
+        config_dir().map(|dir| dir.join("stats.txt"))
+    }
+
+    /// Reads the stats file, starting over if it's missing or unreadable
+    pub fn load() -> Stats {
+        Self::path()
+            .and_then(|path| fs::read_to_string(path).ok())
+            .map(|text| Self::parse(&text))
+            .unwrap_or_default()
+    }
+
+    /// Lines that don't make sense are skipped rather than losing the rest of the file
+    pub fn parse(text: &str) -> Stats {
+        let mut records = BTreeMap::new();
+        let Some((version, lines)) = format::header(text) else {
+            return Stats { records };
+        };
+        let time_scale = match version {
+            1 => 1000,
+            VERSION => 1,
+            _ => return Stats { records },
+        };
+        for line in lines {
+            if let Some((board, record)) = parse_line(line, time_scale) {
+                records.insert(board, record);
+            }
+        }
+        Stats { records }
+    }
+
+    pub fn save(&self) -> io::Result<()> {
+        paths::write("stats.txt", self.to_string())
+    }
+
+    pub fn get(&self, board: Board) -> Record {
+        self.records.get(&board).copied().unwrap_or_default()
+    }
+
+    /// Adds a finished game, anything still being played or practice that [doesn't count](Data::counts_for_records) is ignored
+    pub fn record(&mut self, data: &Data) {
+        let status = data.status();
+        if !status.is_finished() || !data.counts_for_records() {
+            return;
+        }
+        let record = self
+            .records
+            .entry((data.width(), data.height(), data.starting_mines()))
+            .or_default();
+        if status == GameStatus::Won {
+            record.wins += 1;
+            record.streak += 1;
+            record.best_streak = record.best_streak.max(record.streak);
+            if let Some(time) = data.finish_time() {
+                record.best_time = Some(record.best_time.map_or(time, |best| best.min(time)));
+            }
+        } else {
+            record.losses += 1;
+            record.streak = 0;
+        }
+    }
+}
+
+impl std::fmt::Display for Stats {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "version {VERSION}")?;
+        writeln!(
+            f,
+            "# width height mines wins losses streak best_streak best_millis"
+        )?;
+        for (&(width, height, mines), record) in &self.records {
+            write!(
+                f,
+                "{width} {height} {mines} {} {} {} {} ",
+                record.wins, record.losses, record.streak, record.best_streak
+            )?;
+            match record.best_time {
+                Some(time) => writeln!(f, "{time}")?,
+                None => writeln!(f, "-")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Best times are multiplied by `time_scale` to get milliseconds, a time that doesn't fit skips the line
+fn parse_line(line: &str, time_scale: u64) -> Option<(Board, Record)> {
+    let fields = line.split_whitespace().collect::<Vec<_>>();
+    let [width, height, mines, wins, losses, streak, best_streak, best_time] = fields[..] else {
+        return None;
+    };
+    let record = Record {
+        wins: wins.parse().ok()?,
+        losses: losses.parse().ok()?,
+        streak: streak.parse().ok()?,
+        best_streak: best_streak.parse().ok()?,
+        best_time: match best_time {
+            "-" => None,
+            time => Some(time.parse::<u64>().ok()?.checked_mul(time_scale)?),
+        },
+    };
+    Some((
+        (
+            width.parse().ok()?,
+            height.parse().ok()?,
+            mines.parse().ok()?,
+        ),
+        record,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn version_1_times_are_converted_to_milliseconds() {
+        let stats = Stats::parse("version 1\n9 9 10 1 0 1 1 41\n16 16 40 0 1 0 0 -\n");
+        assert_eq!(stats.get((9, 9, 10)).best_time, Some(41_000));
+        assert_eq!(stats.get((16, 16, 40)).losses, 1);
+
+        // a time too long to convert is skipped like any other line that doesn't make sense
+        let stats =
+            Stats::parse("version 1\n9 9 10 1 0 1 1 18446744073709551615\n30 16 99 2 0 2 2 90\n");
+        assert_eq!(stats.get((9, 9, 10)), Record::default());
+        assert_eq!(stats.get((30, 16, 99)).best_time, Some(90_000));
+
+        let stats = Stats::parse("version 2\n9 9 10 1 0 1 1 18446744073709551615\n");
+        assert_eq!(stats.get((9, 9, 10)).best_time, Some(u64::MAX));
+    }
+
+    fn won(seed: u64) -> Data {
+        let mut data = Data::with_seed(10, 9, 9, seed);
+        for (x, y) in (0..81).map(|index| (index % 9, index / 9)) {
+            if !data.get(x, y).is_mine() {
+                data.reveal(x, y);
+            }
+        }
+        assert_eq!(data.status(), GameStatus::Won);
+        data
+    }
+
+    fn lost(seed: u64) -> Data {
+        let mut data = Data::with_seed(10, 9, 9, seed);
+        data.reveal(4, 4);
+        let mine = (0..81)
+            .map(|index| (index % 9, index / 9))
+            .find(|&(x, y)| data.get(x, y).is_mine())
+            .unwrap();
+        data.reveal(mine.0, mine.1);
+        assert_eq!(data.status(), GameStatus::Lost);
+        data
+    }
+
+    #[test]
+    fn games_add_up_to_streaks_and_best_times() {
+        let mut stats = Stats::default();
+        let games = [won(1), won(2), lost(3), won(4)];
+        for game in &games {
+            stats.record(game);
+        }
+        let record = stats.get((9, 9, 10));
+        assert_eq!((record.wins, record.losses), (3, 1));
+        assert_eq!((record.streak, record.best_streak), (1, 2));
+        let best = games
+            .iter()
+            .filter(|game| game.status() == GameStatus::Won)
+            .filter_map(Data::finish_time)
+            .min();
+        assert_eq!(record.best_time, best);
+        assert_eq!(stats.get((16, 16, 40)), Record::default());
+    }
+
+    #[test]
+    fn unfinished_and_practice_games_are_ignored() {
+        let mut stats = Stats::default();
+        let mut playing = Data::with_seed(10, 9, 9, 1);
+        stats.record(&playing);
+        playing.reveal(4, 4);
+        stats.record(&playing);
+
+        let mut undone = lost(3);
+        undone.undo();
+        undone.redo();
+        assert!(!undone.counts_for_records());
+        stats.record(&undone);
+
+        let mut retried = lost(3);
+        retried.retry();
+        let mine = (0..81)
+            .map(|index| (index % 9, index / 9))
+            .find(|&(x, y)| retried.get(x, y).is_mine())
+            .unwrap();
+        retried.reveal(mine.0, mine.1);
+        assert!(retried.status().is_finished() && !retried.counts_for_records());
+        stats.record(&retried);
+
+        assert_eq!(stats.get((9, 9, 10)), Record::default());
+    }
+
+    #[test]
+    fn stats_read_back_the_same_skipping_broken_lines() {
+        let text = "version 2
+# width height mines wins losses streak best_streak best_millis
+9 9 10 12 4 3 5 41274
+9 9 ten 1 1 1 1 -
+16 16 40 1 2
+30 16 99 0 2 0 0 -
+45 42 500 1 0 1 1 -1
+";
+        let stats = Stats::parse(text);
+        assert_eq!(
+            stats.get((9, 9, 10)),
+            Record {
+                wins: 12,
+                losses: 4,
+                streak: 3,
+                best_streak: 5,
+                best_time: Some(41_274),
+            }
+        );
+        assert_eq!(stats.get((30, 16, 99)).losses, 2);
+        assert_eq!(stats.records.len(), 2);
+        assert_eq!(Stats::parse(&stats.to_string()).records, stats.records);
+
+        assert!(Stats::parse("version 9\n9 9 10 1 0 1 1 -\n")
+            .records
+            .is_empty());
+        assert!(Stats::parse("9 9 10 1 0 1 1 -\n").records.is_empty());
+    }
+}
@@ -0,0 +1,140 @@
+/// A single square of the board, packed into one byte
+///
+/// | bits | meaning                       |
+/// |------|-------------------------------|
+/// | 0    | flagged                       |
+/// | 1    | revealed                      |
+/// | 2    | mine                          |
+/// | 3    | question mark                 |
+/// | 4-7  | neighbouring mines (`0..=8`)  |
+#[derive(Copy, Clone, Default, PartialEq, Eq, Hash)]
+#[repr(transparent)]
+pub struct Tile(u8);
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TileContent {
+    Mine,
+    /// The amount of neighbouring mines, `Number(0)` is an empty tile
+    Number(u8),
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum TileState {
+    Hidden,
+    Revealed,
+    Flagged,
+    Question,
+}
+
+impl Tile {
+    const FLAGGED: u8 = 0b0001;
+    const REVEALED: u8 = 0b0010;
+    const MINE: u8 = 0b0100;
+    const QUESTION: u8 = 0b1000;
+    const STATE: u8 = Self::FLAGGED | Self::REVEALED | Self::QUESTION;
+
+    /// A hidden tile with no mine and no neighbouring mines
+    pub const EMPTY: Tile = Tile(0);
+
+    #[inline]
+    pub const fn content(self) -> TileContent {
+        if self.is_mine() {
+            TileContent::Mine
+        } else {
+            TileContent::Number(self.neighbouring_mines())
+        }
+    }
+
+    #[inline]
+    pub const fn state(self) -> TileState {
+        if self.0 & Self::REVEALED != 0 {
+            TileState::Revealed
+        } else if self.0 & Self::FLAGGED != 0 {
+            TileState::Flagged
+        } else if self.0 & Self::QUESTION != 0 {
+            TileState::Question
+        } else {
+            TileState::Hidden
+        }
+    }
+
+    #[inline]
+    pub const fn is_mine(self) -> bool {
+        self.0 & Self::MINE != 0
+    }
+
+    #[inline]
+    pub const fn is_revealed(self) -> bool {
+        self.0 & Self::REVEALED != 0
+    }
+
+    #[inline]
+    pub const fn is_flagged(self) -> bool {
+        self.0 & Self::FLAGGED != 0
+    }
+
+    /// The amount of mines surrounding this tile, including for mines themselves
+    #[inline]
+    pub const fn neighbouring_mines(self) -> u8 {
+        self.0 >> 4
+    }
+
+    /// The packed byte, as laid out above
+    #[inline]
+    pub(crate) const fn bits(self) -> u8 {
+        self.0
+    }
+
+    #[inline]
+    pub(crate) const fn from_bits(bits: u8) -> Tile {
+        Tile(bits)
+    }
+
+    #[inline]
+    pub(crate) fn set_state(&mut self, state: TileState) {
+        self.0 = (self.0 & !Self::STATE)
+            | match state {
+                TileState::Hidden => 0,
+                TileState::Revealed => Self::REVEALED,
+                TileState::Flagged => Self::FLAGGED,
+                TileState::Question => Self::QUESTION,
+            };
+    }
+
+    #[inline]
+    pub(crate) fn set_mine(&mut self) {
+        self.0 |= Self::MINE;
+    }
+
+    /// Removes the mine and neighbour count, keeping the state
+    #[inline]
+    pub(crate) fn clear_content(&mut self) {
+        self.0 &= Self::STATE;
+    }
+
+    #[inline]
+    pub(crate) fn clear_mine(&mut self) {
+        self.0 &= !Self::MINE;
+    }
+
+    #[inline]
+    pub(crate) fn add_neighbouring_mine(&mut self) {
+        debug_assert!(self.neighbouring_mines() < 8);
+        self.0 += 1 << 4;
+    }
+
+    #[inline]
+    pub(crate) fn remove_neighbouring_mine(&mut self) {
+        debug_assert!(self.neighbouring_mines() > 0);
+        self.0 -= 1 << 4;
+    }
+}
+
+impl std::fmt::Debug for Tile {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Tile")
+            .field("content", &self.content())
+            .field("state", &self.state())
+            .finish()
+    }
+}
@@ -0,0 +1,283 @@
+//! The same game in a terminal, for when there's no window to open
+//!
+//! Arrow keys or hjkl move the cursor, space reveals, f flags and d chords. u and r undo and redo, n starts a
+//! new board of the same size, b, i and e switch to the standard ones and q quits.
+
+extern crate crossterm;
+extern crate minesweeper_rs;
+
+use crossterm::cursor::{Hide, MoveTo, Show};
+use crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use crossterm::style::{
+    Attribute, Color, Print, SetAttribute, SetBackgroundColor, SetForegroundColor,
+};
+use crossterm::terminal::{self, Clear, ClearType, EnterAlternateScreen, LeaveAlternateScreen};
+use crossterm::{execute, queue};
+use minesweeper_rs::args::{self, BoardOptions};
+use minesweeper_rs::config::Config;
+use minesweeper_rs::stats::Stats;
+use minesweeper_rs::{Data, GameConfig, GameStatus, TileContent, TileState};
+use std::io::{self, Write};
+use std::time::Duration;
+
+/// Lines above the board, for the counters and the face
+const HEADER: u16 = 2;
+/// Every tile takes two columns so the board comes out roughly square
+const TILE_WIDTH: u16 = 2;
+
+struct Game {
+    data: Data,
+    cursor: (usize, usize),
+    stats: Stats,
+}
+
+fn main() {
+    let config = Config::load();
+    match parse(std::env::args().skip(1), &config) {
+        Ok(Some(board)) => {
+            let mut game = Game {
+                cursor: (board.width / 2, board.height / 2),
+                data: Data::from_config(board),
+                stats: Stats::load(),
+            };
+            game.data.set_question_marks(config.question_marks);
+            if let Err(error) = run(&mut game) {
+                eprintln!("error: {error}");
+                std::process::exit(1)
+            }
+        }
+        Ok(None) => println!("{}", usage()),
+        Err(error) => {
+            eprintln!("error: {error}\n{}", usage());
+            std::process::exit(2)
+        }
+    }
+}
+
+fn usage() -> String {
+    args::usage(
+        "minesweeper-tui",
+        "any size, the board scrolls",
+        "any size, the board scrolls",
+        &[],
+    )
+}
+
+/// `Ok(None)` when only the usage was asked for
+fn parse(
+    mut args: impl Iterator<Item = String>,
+    config: &Config,
+) -> Result<Option<GameConfig>, String> {
+    let mut board = BoardOptions::new(config, check_board);
+    while let Some(arg) = args.next() {
+        let value = |expected: &str| {
+            args.next()
+                .ok_or_else(|| format!("{arg} expects {expected}"))
+        };
+        if board.parse(&arg, value)? {
+            continue;
+        }
+        match arg.as_str() {
+            "--help" | "-h" => return Ok(None),
+            _ => return Err(format!("unknown argument {arg}")),
+        }
+    }
+    let board = board.board();
+    check_board(board)?;
+    Ok(Some(board))
+}
+
+/// Any size goes as long as the counters can hold it, the terminal scrolls to whatever doesn't fit
+fn check_board(game: GameConfig) -> Result<(), String> {
+    let tiles = game
+        .tile_count()
+        .filter(|_| game.width > 0 && game.height > 0);
+    match tiles {
+        Some(tiles) if tiles.saturating_sub(9) >= game.mines => Ok(()),
+        Some(_) => Err(format!(
+            "{} mines don't fit on a {}x{} board",
+            game.mines, game.width, game.height
+        )),
+        None => Err(format!(
+            "a board can't be {}x{} tiles",
+            game.width, game.height
+        )),
+    }
+}
+
+/// Takes over the terminal until the player quits, leaving it as it was even if drawing fails
+fn run(game: &mut Game) -> io::Result<()> {
+    let mut stdout = io::stdout();
+    terminal::enable_raw_mode()?;
+    execute!(stdout, EnterAlternateScreen, Hide)?;
+    let result = play(game, &mut stdout);
+    execute!(stdout, Show, LeaveAlternateScreen)?;
+    terminal::disable_raw_mode()?;
+    result
+}
+
+fn play(game: &mut Game, out: &mut impl Write) -> io::Result<()> {
+    loop {
+        draw(game, out)?;
+        // wakes up now and then to keep the clock ticking
+        if !event::poll(Duration::from_millis(100))? {
+            continue;
+        }
+        let carry_on = match event::read()? {
+            Event::Key(key) if key.kind != KeyEventKind::Release => key_input(game, key),
+            Event::Resize(..) => {
+                execute!(out, Clear(ClearType::All))?;
+                true
+            }
+            _ => true,
+        };
+        if !carry_on {
+            return Ok(());
+        }
+    }
+}
+
+/// Returns whether to carry on playing
+fn key_input(game: &mut Game, key: KeyEvent) -> bool {
+    let data = &mut game.data;
+    let (x, y) = game.cursor;
+    let status = data.status();
+    match key.code {
+        KeyCode::Char('q') | KeyCode::Esc => return false,
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+        KeyCode::Left | KeyCode::Char('h') => game.cursor.0 = x.saturating_sub(1),
+        KeyCode::Right | KeyCode::Char('l') => game.cursor.0 = (x + 1).min(data.width() - 1),
+        KeyCode::Up | KeyCode::Char('k') => game.cursor.1 = y.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => game.cursor.1 = (y + 1).min(data.height() - 1),
+        KeyCode::Char(' ') | KeyCode::Enter => data.reveal(x, y),
+        KeyCode::Char('f') => data.flag(x, y),
+        KeyCode::Char('d') => data.chord(x, y),
+        KeyCode::Char('u') => {
+            data.undo();
+        }
+        KeyCode::Char('r') => {
+            data.redo();
+        }
+        KeyCode::Char('n') => data.restart(),
+        KeyCode::Char('b') => new_board(game, GameConfig::BEGINNER),
+        KeyCode::Char('i') => new_board(game, GameConfig::INTERMEDIATE),
+        KeyCode::Char('e') => new_board(game, GameConfig::EXPERT),
+        _ => {}
+    }
+    if !status.is_finished() && game.data.status().is_finished() {
+        game.stats.record(&game.data);
+        // nowhere to show it without messing up the screen, the stats just miss this game
+        let _ = game.stats.save();
+    }
+    true
+}
+
+fn new_board(game: &mut Game, config: GameConfig) {
+    let no_guess = game.data.no_guess();
+    game.data.new_game(GameConfig { no_guess, ..config });
+    game.cursor = (config.width / 2, config.height / 2);
+}
+
+fn draw(game: &Game, out: &mut impl Write) -> io::Result<()> {
+    let data = &game.data;
+    let (columns, rows) = terminal::size()?;
+    // as much of the board as fits, scrolled to keep the cursor in view
+    let visible_width = (columns / TILE_WIDTH).max(1) as usize;
+    let visible_height = rows.saturating_sub(HEADER).max(1) as usize;
+    let scroll = |cursor: usize, visible: usize, size: usize| {
+        cursor
+            .saturating_sub(visible / 2)
+            .min(size.saturating_sub(visible))
+    };
+    let left = scroll(game.cursor.0, visible_width, data.width());
+    let top = scroll(game.cursor.1, visible_height, data.height());
+
+    let face = match data.status() {
+        GameStatus::Won => "8)",
+        GameStatus::Lost => "x(",
+        GameStatus::Ready | GameStatus::Playing => ":)",
+    };
+    let (time, dot) = data.clock();
+    let time = match dot {
+        Some(index) => format!("{}.{}", &time[..=index], &time[index + 1..]),
+        None => time,
+    };
+    // boards can be far wider than the terminal, or a u16
+    let board_width = data
+        .width()
+        .saturating_mul(TILE_WIDTH as usize)
+        .min(columns as usize) as u16;
+    queue!(
+        out,
+        MoveTo(0, 0),
+        Clear(ClearType::CurrentLine),
+        SetForegroundColor(Color::Red),
+        Print(data.mine_counter()),
+        MoveTo((board_width / 2).saturating_sub(1), 0),
+        SetForegroundColor(Color::Yellow),
+        Print(face),
+        MoveTo(board_width.saturating_sub(time.len() as u16), 0),
+        SetForegroundColor(Color::Red),
+        Print(time),
+        SetForegroundColor(Color::Reset),
+    )?;
+
+    for (row, y) in (top..data.height()).take(visible_height).enumerate() {
+        queue!(out, MoveTo(0, HEADER + row as u16))?;
+        for x in (left..data.width()).take(visible_width) {
+            let (symbol, colour, background) = tile(data, x, y);
+            if (x, y) == game.cursor {
+                queue!(out, SetAttribute(Attribute::Reverse))?;
+            }
+            queue!(
+                out,
+                SetBackgroundColor(background),
+                SetForegroundColor(colour),
+                Print(format!(" {symbol}")),
+                SetAttribute(Attribute::Reset),
+                SetBackgroundColor(Color::Reset),
+                SetForegroundColor(Color::Reset),
+            )?;
+        }
+        queue!(out, Clear(ClearType::UntilNewLine))?;
+    }
+    out.flush()
+}
+
+/// What a tile looks like, the same information `tile_sprite` picks a sprite from
+fn tile(data: &Data, x: usize, y: usize) -> (char, Color, Color) {
+    let tile = data.get(x, y);
+    if let Some(death_pos) = data.death_pos() {
+        if tile.is_mine() && (x, y) == death_pos {
+            return ('*', Color::Black, Color::Red);
+        }
+        if tile.is_mine() {
+            return ('*', Color::White, Color::Reset);
+        }
+        if tile.is_flagged() {
+            return ('X', Color::Red, Color::Reset);
+        }
+    }
+    match tile.state() {
+        TileState::Hidden => ('.', Color::DarkGrey, Color::Reset),
+        TileState::Flagged => ('F', Color::Red, Color::Reset),
+        TileState::Question => ('?', Color::White, Color::Reset),
+        TileState::Revealed => match tile.content() {
+            TileContent::Mine => ('*', Color::Black, Color::Red),
+            TileContent::Number(0) => (' ', Color::Reset, Color::Reset),
+            TileContent::Number(count) => {
+                let colour = match count {
+                    1 => Color::Blue,
+                    2 => Color::Green,
+                    3 => Color::Red,
+                    4 => Color::DarkBlue,
+                    5 => Color::DarkRed,
+                    6 => Color::Cyan,
+                    7 => Color::Magenta,
+                    _ => Color::Grey,
+                };
+                ((b'0' + count) as char, colour, Color::Reset)
+            }
+        },
+    }
+}
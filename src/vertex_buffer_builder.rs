@@ -1,3 +1,4 @@
+use crate::sprites::Sprite;
 use winit::dpi::PhysicalSize;
 
 pub struct VertexBufferBuilder {
@@ -7,11 +8,15 @@ pub struct VertexBufferBuilder {
     window_width: f32,
     window_height: f32,
     texture_width: f32,
-    texture_height: f32
+    texture_height: f32,
 }
 
 impl VertexBufferBuilder {
-    pub fn new(size: &PhysicalSize<u32>, texture_width: u32, texture_height: u32) -> VertexBufferBuilder {
+    pub fn new(
+        size: &PhysicalSize<u32>,
+        texture_width: u32,
+        texture_height: u32,
+    ) -> VertexBufferBuilder {
         VertexBufferBuilder {
             vertices: Vec::with_capacity(393216),
             indices: Vec::with_capacity(131072),
@@ -19,7 +24,7 @@ impl VertexBufferBuilder {
             window_width: size.width as f32,
             window_height: size.height as f32,
             texture_width: texture_width as f32,
-            texture_height: texture_height as f32
+            texture_height: texture_height as f32,
         }
     }
 
@@ -47,6 +52,17 @@ impl VertexBufferBuilder {
         (self.indices.len() >> 1) as u32
     }
 
+    /// Draws the whole sprite at its own size
+    #[inline]
+    pub fn draw_sprite(&mut self, pos: (u32, u32), sprite: Sprite) {
+        self.draw_texture(pos, sprite.uv, sprite.size);
+    }
+
+    #[inline]
+    pub fn draw_sprite_tinted(&mut self, pos: (u32, u32), sprite: Sprite, tint: [u8; 4]) {
+        self.draw_texture_tinted(pos, sprite.uv, sprite.size, tint);
+    }
+
     #[inline]
     pub fn draw_texture(&mut self, pos: (u32, u32), uv: (u32, u32), dims: (u32, u32)) {
         self.draw_texture_z(pos, 0.0, uv, dims);
@@ -54,6 +70,32 @@ impl VertexBufferBuilder {
 
     #[inline]
     pub fn draw_texture_z(&mut self, pos: (u32, u32), z: f32, uv: (u32, u32), dims: (u32, u32)) {
+        self.draw_texture_z_tinted(pos, z, uv, dims, [0xFF; 4]);
+    }
+
+    /// Multiplies every texel by an rgba colour
+    #[inline]
+    pub fn draw_texture_tinted(
+        &mut self,
+        pos: (u32, u32),
+        uv: (u32, u32),
+        dims: (u32, u32),
+        tint: [u8; 4],
+    ) {
+        self.draw_texture_z_tinted(pos, 0.0, uv, dims, tint);
+    }
+
+    #[inline]
+    pub fn draw_texture_z_tinted(
+        &mut self,
+        pos: (u32, u32),
+        z: f32,
+        uv: (u32, u32),
+        dims: (u32, u32),
+        tint: [u8; 4],
+    ) {
+        self.vertices.reserve(96);
+        self.indices.reserve(12);
         unsafe {
             let x = pos.0 as f32;
             let y = pos.1 as f32;
@@ -70,7 +112,8 @@ impl VertexBufferBuilder {
             let u1 = (u + width) / self.texture_width;
             let v0 = v / self.texture_height;
             let v1 = (v + height) / self.texture_height;
-            let z = z;
+
+            let tint = u32::from_ne_bytes(tint);
 
             let len = self.vertices_len;
             let vec = &mut self.vertices;
@@ -78,46 +121,50 @@ impl VertexBufferBuilder {
             let vertices_len = vec.len();
             let ptr = vec.as_mut_ptr().add(vertices_len) as *mut f32;
             // top left
-            *ptr                = x1;
+            *ptr = x1;
             *(ptr.add(1)) = y1;
             *(ptr.add(2)) = z;
             *(ptr.add(3)) = u1;
             *(ptr.add(4)) = v0;
+            *(ptr.add(5) as *mut u32) = tint;
             // top right
-            *(ptr.add(5)) = x0;
-            *(ptr.add(6)) = y1;
-            *(ptr.add(7)) = z;
-            *(ptr.add(8)) = u0;
-            *(ptr.add(9)) = v0;
+            *(ptr.add(6)) = x0;
+            *(ptr.add(7)) = y1;
+            *(ptr.add(8)) = z;
+            *(ptr.add(9)) = u0;
+            *(ptr.add(10)) = v0;
+            *(ptr.add(11) as *mut u32) = tint;
             // bottom left
-            *(ptr.add(10)) = x0;
-            *(ptr.add(11)) = y0;
-            *(ptr.add(12)) = z;
-            *(ptr.add(13)) = u0;
-            *(ptr.add(14)) = v1;
+            *(ptr.add(12)) = x0;
+            *(ptr.add(13)) = y0;
+            *(ptr.add(14)) = z;
+            *(ptr.add(15)) = u0;
+            *(ptr.add(16)) = v1;
+            *(ptr.add(17) as *mut u32) = tint;
             // bottom right
-            *(ptr.add(15)) = x1;
-            *(ptr.add(16)) = y0;
-            *(ptr.add(17)) = z;
-            *(ptr.add(18)) = u1;
-            *(ptr.add(19)) = v1;
+            *(ptr.add(18)) = x1;
+            *(ptr.add(19)) = y0;
+            *(ptr.add(20)) = z;
+            *(ptr.add(21)) = u1;
+            *(ptr.add(22)) = v1;
+            *(ptr.add(23) as *mut u32) = tint;
 
-            vec.set_len(vertices_len + 80);
+            vec.set_len(vertices_len + 96);
 
             let indices_len = self.indices.len();
             let ptr = self.indices.as_mut_ptr().add(indices_len);
 
-            *ptr                 =   len            as u8;
-            *(ptr.add(1))  =  (len >> 8)      as u8;
-            *(ptr.add(2))  =  (len + 1)       as u8;
-            *(ptr.add(3))  = ((len + 1) >> 8) as u8;
-            *(ptr.add(4))  =  (len + 2)       as u8;
-            *(ptr.add(5))  = ((len + 2) >> 8) as u8;
-            *(ptr.add(6))  = *ptr;
-            *(ptr.add(7))  = *(ptr.add(1));
-            *(ptr.add(8))  = *(ptr.add(4));
-            *(ptr.add(9))  = *(ptr.add(5));
-            *(ptr.add(10)) =  (len + 3)       as u8;
+            *ptr = len as u8;
+            *(ptr.add(1)) = (len >> 8) as u8;
+            *(ptr.add(2)) = (len + 1) as u8;
+            *(ptr.add(3)) = ((len + 1) >> 8) as u8;
+            *(ptr.add(4)) = (len + 2) as u8;
+            *(ptr.add(5)) = ((len + 2) >> 8) as u8;
+            *(ptr.add(6)) = *ptr;
+            *(ptr.add(7)) = *(ptr.add(1));
+            *(ptr.add(8)) = *(ptr.add(4));
+            *(ptr.add(9)) = *(ptr.add(5));
+            *(ptr.add(10)) = (len + 3) as u8;
             *(ptr.add(11)) = ((len + 3) >> 8) as u8;
 
             self.indices.set_len(indices_len + 12);
@@ -1,68 +1,133 @@
 use std::num::NonZeroU32;
 
-use wgpu::*;
 use wgpu::util::{BufferInitDescriptor, DeviceExt};
-use winit::{event::*, event_loop::{ControlFlow, EventLoop}, window::WindowBuilder};
+use wgpu::*;
 use winit::dpi::PhysicalSize;
 use winit::window::Icon;
 use winit::window::Window;
+use winit::{
+    event::*,
+    event_loop::{ControlFlow, EventLoop},
+    window::WindowBuilder,
+};
 
-use crate::{assets, key_input, on_resize};
-use crate::{render, mouse_click, Data, mouse_moved};
+use crate::cli::Options;
+use crate::pack::Atlases;
+use crate::rasterizer::background;
+use crate::sprites;
 use crate::vertex_buffer_builder::VertexBufferBuilder;
+use crate::{advance_playback, assets, key_input, on_close, on_resize, title, Playback, Ui};
+use crate::{mouse_click, mouse_moved, render, Clock, Data, MAX_SIZE, MIN_SIZE};
+use minesweeper_rs::config::Config;
+pub use minesweeper_rs::config::Theme;
+use minesweeper_rs::save::SavedGame;
+use minesweeper_rs::stats::Stats;
+use minesweeper_rs::GameConfig;
 
-pub async fn run() {
-    let width = 9;
-    let height = 9;
-    let mine_count: u16 = 10;
+pub async fn run(options: Options, config: Config) {
+    let Options {
+        width,
+        height,
+        mines,
+        seed,
+        no_guess,
+        theme,
+        pack,
+        replay,
+    } = options;
     let event_loop = EventLoop::new();
     let flagged: Vec<u8> = assets::ICON.to_vec();
 
-    let mut data: Data = Data::new(mine_count, width, height);
-    let max_size = PhysicalSize::new(20 + 16 * 45, 20 + 16 * 45);
-    let mut window = WindowBuilder::new().with_title("Minesweeper <3").with_window_icon(Some(Icon::from_rgba(flagged, 16, 16).unwrap())).with_resizable(true).with_min_inner_size(PhysicalSize::new(20 + 16 * 8, 63 + 16 * 3)).with_max_inner_size(max_size.clone()).with_inner_size(PhysicalSize::new((20 + 16 * width) as u32, (63 + 16 * height) as u32)).build(&event_loop).unwrap();
-    let mut state = State::new(&window).await;
+    let mut data: Data = match &replay {
+        Some(replay) => replay.data(),
+        None => Data::from_config(GameConfig {
+            width,
+            height,
+            mines,
+            seed,
+            no_guess,
+        }),
+    };
+    if replay.is_none() {
+        data.set_question_marks(config.question_marks);
+    }
+    let mut ui = Ui {
+        stats: Stats::load(),
+        config,
+        resume: if replay.is_none() {
+            SavedGame::load()
+        } else {
+            None
+        },
+        playback: replay.map(Playback::new),
+        ..Default::default()
+    };
+    let mut shown_title = title(&data, &ui);
+    let max_size = PhysicalSize::new((20 + 16 * MAX_SIZE.0) as u32, (63 + 16 * MAX_SIZE.1) as u32);
+    let mut window = WindowBuilder::new()
+        .with_title(&shown_title)
+        .with_window_icon(Some(Icon::from_rgba(flagged, 16, 16).unwrap()))
+        .with_resizable(true)
+        .with_min_inner_size(PhysicalSize::new(
+            (20 + 16 * MIN_SIZE.0) as u32,
+            (63 + 16 * MIN_SIZE.1) as u32,
+        ))
+        .with_max_inner_size(max_size)
+        .with_inner_size(PhysicalSize::new(
+            (20 + 16 * width) as u32,
+            (63 + 16 * height) as u32,
+        ))
+        .build(&event_loop)
+        .unwrap();
+    let mut state = State::new(&window, theme, Atlases::with_pack(pack.as_deref())).await;
 
-    event_loop.run(move |event, _, control_flow| {
-        match event {
-            Event::RedrawRequested(window_id) if window_id == window.id() => {
-                match state.render(&mut data) {
-                    Ok(_) => {}
-                    Err(SurfaceError::Lost) => state.resize(state.size),
-                    Err(SurfaceError::OutOfMemory) => *control_flow = ControlFlow::Exit,
-                    Err(_) => {}
-                }
+    event_loop.run(move |event, _, control_flow| match event {
+        Event::RedrawRequested(window_id) if window_id == window.id() => {
+            match state.render(&data, &ui) {
+                Ok(_) => {}
+                Err(SurfaceError::Lost) => state.resize(state.size),
+                Err(SurfaceError::OutOfMemory) => *control_flow = ControlFlow::Exit,
+                Err(_) => {}
             }
-            Event::MainEventsCleared => {
-                window.request_redraw();
+        }
+        Event::MainEventsCleared => {
+            advance_playback(&mut data, &mut ui);
+            let title = title(&data, &ui);
+            if title != shown_title {
+                window.set_title(&title);
+                shown_title = title;
             }
-            Event::WindowEvent { ref event, window_id } if window_id == window.id() => {
-                match event {
-                    WindowEvent::CloseRequested => *control_flow = ControlFlow::Exit,
-                    WindowEvent::Resized(physical_size) => {
-                        if physical_size.width <= max_size.width && physical_size.height <= max_size.height {
-                            state.resize(*physical_size);
-                        } else {
-                            window.set_maximized(false);
-                        }
-                    }
-                    WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
-                        state.resize(**new_inner_size);
+            window.request_redraw();
+        }
+        Event::WindowEvent {
+            ref event,
+            window_id,
+        } if window_id == window.id() => {
+            match event {
+                WindowEvent::CloseRequested => {
+                    on_close(&data, &ui);
+                    *control_flow = ControlFlow::Exit;
+                }
+                WindowEvent::Resized(physical_size) => {
+                    if physical_size.width <= max_size.width
+                        && physical_size.height <= max_size.height
+                    {
+                        state.resize(*physical_size);
+                    } else {
+                        window.set_maximized(false);
                     }
-                    _ => {}
                 }
-                state.input(&mut data, event, &mut window);
-            },
-            _ => {}
+                WindowEvent::ScaleFactorChanged { new_inner_size, .. } => {
+                    state.resize(**new_inner_size);
+                }
+                _ => {}
+            }
+            state.input(&mut data, &mut ui, event, &mut window);
         }
+        _ => {}
     });
 }
 
-pub enum Theme {
-    Dark,
-    Light
-}
-
 pub struct State {
     surface: Surface,
     device: Device,
@@ -72,58 +137,58 @@ pub struct State {
     render_pipeline: RenderPipeline,
     dark_diffuse_bind_group: BindGroup,
     light_diffuse_bind_group: BindGroup,
-    pub theme: Theme
+    pub theme: Theme,
+    /// Kept for screenshots to draw with
+    pub atlases: Atlases,
 }
 
 impl State {
-    async fn new(window: &Window) -> Self {
+    /// A random theme unless one is given
+    async fn new(window: &Window, theme: Option<Theme>, atlases: Atlases) -> Self {
         let size = window.inner_size();
 
         let instance = Instance::new(Backends::all());
         let surface = unsafe { instance.create_surface(window) };
-        let adapter = instance
-            .enumerate_adapters(Backends::all())
-            .next()
-            .unwrap();
-        let (device, queue) = adapter.request_device(
-            &DeviceDescriptor {
-                features: Features::empty(),
-                limits: if cfg!(target_arch = "wasm32") {
-                    Limits::downlevel_webgl2_defaults()
-                } else {
-                    Limits::default()
+        let adapter = instance.enumerate_adapters(Backends::all()).next().unwrap();
+        let (device, queue) = adapter
+            .request_device(
+                &DeviceDescriptor {
+                    features: Features::empty(),
+                    limits: if cfg!(target_arch = "wasm32") {
+                        Limits::downlevel_webgl2_defaults()
+                    } else {
+                        Limits::default()
+                    },
+                    label: None,
                 },
-                label: None,
-            },
-            None,
-        ).await.unwrap();
+                None,
+            )
+            .await
+            .unwrap();
         let config = SurfaceConfiguration {
             usage: TextureUsages::RENDER_ATTACHMENT,
-            format: *surface.get_supported_formats(&adapter).get(0).unwrap(),
+            format: *surface.get_supported_formats(&adapter).first().unwrap(),
             width: size.width,
             height: size.height,
             present_mode: PresentMode::Fifo, // fifo = vsync, immediate = no vsync, i want a framerate
-            alpha_mode: CompositeAlphaMode::Auto
+            alpha_mode: CompositeAlphaMode::Auto,
         };
         surface.configure(&device, &config);
-        let width = 256;
-        let height = 256;
+        let (width, height) = sprites::BUILT_IN.size;
         let texture_size = Extent3d {
             width,
             height,
             depth_or_array_layers: 1,
         };
-        let dark_diffuse_texture = device.create_texture(
-            &TextureDescriptor {
-                size: texture_size,
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: TextureDimension::D2,
-                format: TextureFormat::Rgba8UnormSrgb,
-                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
-                label: Some("dark_diffuse_texture"),
-            }
-        );
+        let dark_diffuse_texture = device.create_texture(&TextureDescriptor {
+            size: texture_size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8UnormSrgb,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            label: Some("dark_diffuse_texture"),
+        });
         queue.write_texture(
             ImageCopyTexture {
                 texture: &dark_diffuse_texture,
@@ -131,7 +196,7 @@ impl State {
                 origin: Origin3d::ZERO,
                 aspect: TextureAspect::All,
             },
-            assets::DARK_ATLAS,
+            atlases.get(Theme::Dark),
             ImageDataLayout {
                 offset: 0,
                 bytes_per_row: NonZeroU32::new(4 * width),
@@ -139,17 +204,15 @@ impl State {
             },
             texture_size,
         );
-        let light_diffuse_texture = device.create_texture(
-            &TextureDescriptor {
-                size: texture_size,
-                mip_level_count: 1,
-                sample_count: 1,
-                dimension: TextureDimension::D2,
-                format: TextureFormat::Rgba8UnormSrgb,
-                usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
-                label: Some("light_diffuse_texture")
-            }
-        );
+        let light_diffuse_texture = device.create_texture(&TextureDescriptor {
+            size: texture_size,
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: TextureFormat::Rgba8UnormSrgb,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            label: Some("light_diffuse_texture"),
+        });
         queue.write_texture(
             ImageCopyTexture {
                 texture: &light_diffuse_texture,
@@ -157,7 +220,7 @@ impl State {
                 origin: Origin3d::ZERO,
                 aspect: TextureAspect::All,
             },
-            assets::LIGHT_ATLAS,
+            atlases.get(Theme::Light),
             ImageDataLayout {
                 offset: 0,
                 bytes_per_row: NonZeroU32::new(4 * width),
@@ -165,8 +228,10 @@ impl State {
             },
             texture_size,
         );
-        let dark_diffuse_texture_view = dark_diffuse_texture.create_view(&TextureViewDescriptor::default());
-        let light_diffuse_texture_view = light_diffuse_texture.create_view(&TextureViewDescriptor::default());
+        let dark_diffuse_texture_view =
+            dark_diffuse_texture.create_view(&TextureViewDescriptor::default());
+        let light_diffuse_texture_view =
+            light_diffuse_texture.create_view(&TextureViewDescriptor::default());
         let diffuse_sampler = device.create_sampler(&SamplerDescriptor {
             address_mode_u: AddressMode::Repeat,
             address_mode_v: AddressMode::Repeat,
@@ -199,38 +264,34 @@ impl State {
                 label: Some("texture_bind_group_layout"),
             });
 
-        let dark_diffuse_bind_group = device.create_bind_group(
-            &BindGroupDescriptor {
-                layout: &texture_bind_group_layout,
-                entries: &[
-                    BindGroupEntry {
-                        binding: 0,
-                        resource: BindingResource::TextureView(&dark_diffuse_texture_view),
-                    },
-                    BindGroupEntry {
-                        binding: 1,
-                        resource: BindingResource::Sampler(&diffuse_sampler),
-                    }
-                ],
-                label: Some("dark_diffuse_bind_group"),
-            }
-        );
-        let light_diffuse_bind_group = device.create_bind_group(
-            &BindGroupDescriptor {
-                layout: &texture_bind_group_layout,
-                entries: &[
-                    BindGroupEntry {
-                        binding: 0,
-                        resource: BindingResource::TextureView(&light_diffuse_texture_view),
-                    },
-                    BindGroupEntry {
-                        binding: 1,
-                        resource: BindingResource::Sampler(&diffuse_sampler),
-                    },
-                ],
-                label: Some("light_diffuse_bind_group"),
-            }
-        );
+        let dark_diffuse_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            layout: &texture_bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&dark_diffuse_texture_view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(&diffuse_sampler),
+                },
+            ],
+            label: Some("dark_diffuse_bind_group"),
+        });
+        let light_diffuse_bind_group = device.create_bind_group(&BindGroupDescriptor {
+            layout: &texture_bind_group_layout,
+            entries: &[
+                BindGroupEntry {
+                    binding: 0,
+                    resource: BindingResource::TextureView(&light_diffuse_texture_view),
+                },
+                BindGroupEntry {
+                    binding: 1,
+                    resource: BindingResource::Sampler(&diffuse_sampler),
+                },
+            ],
+            label: Some("light_diffuse_bind_group"),
+        });
         let shader = device.create_shader_module(ShaderModuleDescriptor {
             label: Some("Shader"),
             source: ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
@@ -246,13 +307,11 @@ impl State {
             vertex: VertexState {
                 module: &shader,
                 entry_point: "v",
-                buffers: &[
-                    VertexBufferLayout {
-                        array_stride: 20,
-                        step_mode: VertexStepMode::Vertex,
-                        attributes: &vertex_attr_array![0 => Float32x3, 1 => Float32x2]
-                    }
-                ]
+                buffers: &[VertexBufferLayout {
+                    array_stride: 24,
+                    step_mode: VertexStepMode::Vertex,
+                    attributes: &vertex_attr_array![0 => Float32x3, 1 => Float32x2, 2 => Unorm8x4],
+                }],
             },
             fragment: Some(FragmentState {
                 module: &shader,
@@ -290,13 +349,14 @@ impl State {
             render_pipeline,
             dark_diffuse_bind_group,
             light_diffuse_bind_group,
-            theme: {
+            theme: theme.unwrap_or_else(|| {
                 if rand::random::<u8>() > u8::MAX / 2 {
                     Theme::Dark
                 } else {
                     Theme::Light
                 }
-            }
+            }),
+            atlases,
         }
     }
 
@@ -311,9 +371,9 @@ impl State {
     }
 
     #[inline]
-    fn input(&mut self, data: &mut Data, event: &WindowEvent, window: &mut Window) {
+    fn input(&mut self, data: &mut Data, ui: &mut Ui, event: &WindowEvent, window: &mut Window) {
         match event {
-            WindowEvent::Resized(size) => on_resize(*size, data),
+            WindowEvent::Resized(size) => on_resize(*size, data, ui),
             WindowEvent::Moved(_) => (),
             WindowEvent::CloseRequested => (),
             WindowEvent::Destroyed => (),
@@ -322,13 +382,17 @@ impl State {
             WindowEvent::HoveredFileCancelled => (),
             WindowEvent::ReceivedCharacter(_) => (),
             WindowEvent::Focused(_) => (),
-            WindowEvent::KeyboardInput { input, .. } => key_input(*input, data, window, self),
-            WindowEvent::ModifiersChanged(_) => (),
-            WindowEvent::CursorMoved { position, .. } => mouse_moved(position, data, window, self),
+            WindowEvent::KeyboardInput { input, .. } => key_input(*input, data, ui, window, self),
+            WindowEvent::ModifiersChanged(modifiers) => ui.modifiers = *modifiers,
+            WindowEvent::CursorMoved { position, .. } => {
+                mouse_moved(position, data, ui, window, self)
+            }
             WindowEvent::CursorEntered { .. } => (),
             WindowEvent::CursorLeft { .. } => (),
             WindowEvent::MouseWheel { .. } => (),
-            WindowEvent::MouseInput { state, button, .. } => mouse_click(state, button, data, window, self),
+            WindowEvent::MouseInput { state, button, .. } => {
+                mouse_click(state, button, data, ui, window, self)
+            }
             WindowEvent::TouchpadPressure { .. } => (),
             WindowEvent::AxisMotion { .. } => (),
             WindowEvent::Touch(_) => (),
@@ -337,37 +401,32 @@ impl State {
             WindowEvent::Ime(_) => (),
             WindowEvent::Occluded(_) => (),
         }
-
     }
 
-    fn render(&mut self, data: &mut Data) -> Result<(), SurfaceError> {
+    fn render(&mut self, data: &Data, ui: &Ui) -> Result<(), SurfaceError> {
         let vertex_buffer;
         let index_buffer;
         {
             let output: SurfaceTexture = self.surface.get_current_texture()?;
-            let view: TextureView = output.texture.create_view(&TextureViewDescriptor::default());
-            let mut encoder: CommandEncoder = self.device.create_command_encoder(&CommandEncoderDescriptor {
-                label: Some("Render Encoder"),
-            });
+            let view: TextureView = output
+                .texture
+                .create_view(&TextureViewDescriptor::default());
+            let mut encoder: CommandEncoder =
+                self.device
+                    .create_command_encoder(&CommandEncoderDescriptor {
+                        label: Some("Render Encoder"),
+                    });
             let mut render_pass = encoder.begin_render_pass(&RenderPassDescriptor {
                 label: Some("Render Pass"),
                 color_attachments: &[Some(RenderPassColorAttachment {
                     view: &view,
                     resolve_target: None,
                     ops: Operations {
-                        load: LoadOp::Clear(match self.theme {
-                            Theme::Dark => Color {
-                                r: 0.0461488424,
-                                g: 0.0461488424,
-                                b: 0.0461488424,
-                                a: 1.0
-                            },
-                            Theme::Light => Color {
-                                r: 0.535641609,
-                                g: 0.535641609,
-                                b: 0.535641609,
-                                a: 1.0
-                            }
+                        load: LoadOp::Clear(Color {
+                            r: background(self.theme),
+                            g: background(self.theme),
+                            b: background(self.theme),
+                            a: 1.0,
                         }),
                         store: true,
                     },
@@ -378,25 +437,25 @@ impl State {
             render_pass.set_pipeline(&self.render_pipeline);
             match self.theme {
                 Theme::Dark => render_pass.set_bind_group(0, &self.dark_diffuse_bind_group, &[]),
-                Theme::Light => render_pass.set_bind_group(0, &self.light_diffuse_bind_group, &[])
+                Theme::Light => render_pass.set_bind_group(0, &self.light_diffuse_bind_group, &[]),
             }
 
-            let mut vertex_buffer_builder = VertexBufferBuilder::new(&self.size, 256, 256);
-            render(&mut vertex_buffer_builder, data);
-            vertex_buffer = self.device.create_buffer_init(
-                &BufferInitDescriptor {
-                    label: Some("Vertex Buffer"),
-                    contents: vertex_buffer_builder.vertices(),
-                    usage: BufferUsages::VERTEX,
-                }
-            );
-            index_buffer = self.device.create_buffer_init(
-                &BufferInitDescriptor {
-                    label: Some("Index Buffer"),
-                    contents: vertex_buffer_builder.indices(),
-                    usage: BufferUsages::INDEX,
-                }
+            let mut vertex_buffer_builder = VertexBufferBuilder::new(
+                &self.size,
+                sprites::BUILT_IN.size.0,
+                sprites::BUILT_IN.size.1,
             );
+            render(&mut vertex_buffer_builder, data, ui, Clock::now(data));
+            vertex_buffer = self.device.create_buffer_init(&BufferInitDescriptor {
+                label: Some("Vertex Buffer"),
+                contents: vertex_buffer_builder.vertices(),
+                usage: BufferUsages::VERTEX,
+            });
+            index_buffer = self.device.create_buffer_init(&BufferInitDescriptor {
+                label: Some("Index Buffer"),
+                contents: vertex_buffer_builder.indices(),
+                usage: BufferUsages::INDEX,
+            });
             render_pass.set_vertex_buffer(0, vertex_buffer.slice(..));
             render_pass.set_index_buffer(index_buffer.slice(..), IndexFormat::Uint16);
 
@@ -406,8 +465,7 @@ impl State {
 
             self.queue.submit(std::iter::once(encoder.finish()));
             output.present();
-
         }
         Ok(())
     }
-}
\ No newline at end of file
+}